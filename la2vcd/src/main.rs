@@ -0,0 +1,175 @@
+//! Converts the output of the `logic` bin to a VCD file
+//!
+//! Reads the `probe-run` output from a file or stdin and writes VCD to stdout:
+//!
+//! ```console
+//! $ cargo la2vcd capture.txt > capture.vcd
+//! ```
+//!
+//! Only lines containing `la: ` are looked at, everything else the log has in it is skipped.
+//!
+//! The device timestamps are 32 bits and wrap. A timestamp lower than the one before counts as a
+//! wrap, which the device makes sure of by printing a `now` heartbeat well within every wrap
+//! period, even when no pin changes.
+
+use std::{
+    env,
+    fs::File,
+    io::{self, BufRead, BufReader, Write},
+    process,
+};
+
+struct Header {
+    freq: u64,
+    names: Vec<String>,
+}
+
+struct Converter<W: Write> {
+    out: W,
+    header: Option<Header>,
+    // Set once the VCD header has been written
+    start: Option<u64>,
+    // Last raw timestamp and the wraps seen so far, the device counter is only 32 bits
+    last: u32,
+    wraps: u64,
+    levels: u8,
+}
+
+impl<W: Write> Converter<W> {
+    fn new(out: W) -> Self {
+        Converter {
+            out,
+            header: None,
+            start: None,
+            last: 0,
+            wraps: 0,
+            levels: 0,
+        }
+    }
+
+    fn line(&mut self, line: &str) -> Result<(), String> {
+        let rest = match line.find("la: ") {
+            Some(i) => &line[i + 4..],
+            None => return Ok(()),
+        };
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        match words.as_slice() {
+            ["start", freq, n] => {
+                if self.start.is_some() {
+                    return Err("capture restarted, split the log".into());
+                }
+                let n: usize = parse(n)?;
+                self.header = Some(Header {
+                    freq: parse(freq)?,
+                    names: (0..n).map(|i| format!("pin{}", i)).collect(),
+                });
+            }
+            ["pin", i, name] => {
+                let i: usize = parse(i)?;
+                let header = self.header.as_mut().ok_or("pin before start")?;
+                let slot = header.names.get_mut(i).ok_or("pin index out of range")?;
+                *slot = name.to_string();
+            }
+            ["now", at] => {
+                self.header.as_ref().ok_or("heartbeat before start")?;
+                self.extend(parse(at)?);
+            }
+            ["dropped", count] => {
+                eprintln!("warning: {} samples were dropped on the device", count);
+                writeln!(self.out, "$comment {} samples dropped $end", count).map_err(io_err)?;
+            }
+            [at, levels] => self.sample(parse(at)?, parse(levels)?)?,
+            _ => return Err(format!("unrecognized line: {}", line.trim())),
+        }
+        Ok(())
+    }
+
+    // Widens a device timestamp to 64 bits
+    fn extend(&mut self, at: u32) -> u64 {
+        if at < self.last {
+            self.wraps += 1;
+        }
+        self.last = at;
+        (self.wraps << 32) + at as u64
+    }
+
+    fn sample(&mut self, at: u32, levels: u8) -> Result<(), String> {
+        self.header.as_ref().ok_or("sample before start")?;
+        let ticks = self.extend(at);
+        let header = self.header.as_ref().unwrap();
+
+        let start = match self.start {
+            Some(start) => start,
+            None => {
+                write_header(&mut self.out, header).map_err(io_err)?;
+                writeln!(self.out, "#0\n$dumpvars").map_err(io_err)?;
+                for i in 0..header.names.len() {
+                    writeln!(self.out, "{}{}", (levels >> i) & 1, id(i)).map_err(io_err)?;
+                }
+                writeln!(self.out, "$end").map_err(io_err)?;
+                self.start = Some(ticks);
+                self.levels = levels;
+                return Ok(());
+            }
+        };
+
+        let ns = (ticks - start) as u128 * 1_000_000_000 / header.freq as u128;
+        writeln!(self.out, "#{}", ns).map_err(io_err)?;
+        let changed = levels ^ self.levels;
+        for i in (0..header.names.len()).filter(|i| changed & (1 << i) != 0) {
+            writeln!(self.out, "{}{}", (levels >> i) & 1, id(i)).map_err(io_err)?;
+        }
+        self.levels = levels;
+        Ok(())
+    }
+}
+
+fn write_header<W: Write>(out: &mut W, header: &Header) -> io::Result<()> {
+    writeln!(out, "$timescale 1ns $end")?;
+    writeln!(out, "$scope module logic $end")?;
+    for (i, name) in header.names.iter().enumerate() {
+        writeln!(out, "$var wire 1 {} {} $end", id(i), name)?;
+    }
+    writeln!(out, "$upscope $end")?;
+    writeln!(out, "$enddefinitions $end")
+}
+
+// VCD identifiers are printable characters starting at '!'
+fn id(i: usize) -> char {
+    (b'!' + i as u8) as char
+}
+
+fn parse<T: std::str::FromStr>(s: &str) -> Result<T, String> {
+    s.parse().map_err(|_| format!("not a number: {}", s))
+}
+
+fn io_err(e: io::Error) -> String {
+    e.to_string()
+}
+
+fn main() {
+    let input: Box<dyn BufRead> = match env::args().nth(1) {
+        Some(path) => match File::open(&path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                process::exit(1);
+            }
+        },
+        None => Box::new(BufReader::new(io::stdin())),
+    };
+
+    let stdout = io::stdout();
+    let mut converter = Converter::new(stdout.lock());
+    for (n, line) in input.lines().enumerate() {
+        let result = line.map_err(io_err).and_then(|line| converter.line(&line));
+        if let Err(e) = result {
+            eprintln!("line {}: {}", n + 1, e);
+            process::exit(1);
+        }
+    }
+    if converter.start.is_none() {
+        eprintln!("no capture found in the input");
+        process::exit(1);
+    }
+}
@@ -0,0 +1,9 @@
+/* Linker script for the nRF52840 - WITHOUT SOFT DEVICE */
+MEMORY
+{
+  /* NOTE K = KiBi = 1024 bytes */
+  /* The last page is kept for the ranging calibration, see `calibration::FLASH_PAGE` */
+  FLASH : ORIGIN = 0x00000000, LENGTH = 1020K
+  CALIBRATION : ORIGIN = 0x000ff000, LENGTH = 4K
+  RAM : ORIGIN = 0x20000000, LENGTH = 256K
+}
@@ -0,0 +1,160 @@
+// Scheduler for several SRF04 sensors on one board
+//
+// Sensors are fired in groups, one group per slot. Sensors in the same group must not hear each
+// other's pings (e.g. facing away from each other), round-robin puts every sensor in its own
+// group. A slot lasts until the app fires the next group, which should be at least `SLOT_MS`
+// later so that residual echoes of the previous ping have died out.
+//
+// Only the echo pins of the group in flight are bound to GPIOTE channels, so N sensors share
+// channels `0..G` where G is the size of the largest group. The remaining channels are free for
+// the app, see `Srf04Array::gpiote`.
+use crate::{
+    distance::Distance,
+    gpiote::{channel, NUM_CHANNELS},
+    srf04::{Error, Srf04},
+};
+use fugit::TimerInstantU32;
+use nrf52840_hal::gpiote::Gpiote;
+
+/// Minimum time between two groups being fired
+pub const SLOT_MS: u32 = 60;
+
+pub enum Schedule {
+    /// Fire one sensor at a time
+    RoundRobin,
+    /// Fire the sensors of each bitmask together, e.g. `&[0b0101, 0b1010]`
+    Groups(&'static [u8]),
+}
+
+#[derive(Clone, Copy)]
+pub struct Reading<const FREQ: u32> {
+    pub distance: Result<Distance, Error>,
+    /// When the measurement completed
+    pub at: TimerInstantU32<FREQ>,
+}
+
+pub struct Srf04Array<const N: usize, const FREQ: u32> {
+    sensors: [Srf04<FREQ>; N],
+    gpiote: Gpiote,
+    schedule: Schedule,
+    // Next group to fire
+    group: usize,
+    // Sensors still waiting for their echo
+    pending: u8,
+    channels: [Option<usize>; NUM_CHANNELS],
+    readings: [Option<Reading<FREQ>>; N],
+}
+
+impl<const N: usize, const FREQ: u32> Srf04Array<N, FREQ> {
+    pub fn new(sensors: [Srf04<FREQ>; N], gpiote: Gpiote, schedule: Schedule) -> Self {
+        assert!(N <= NUM_CHANNELS, "at most 8 sensors are supported");
+        if let Schedule::Groups(groups) = schedule {
+            assert!(!groups.is_empty());
+            assert!(groups.iter().all(|&g| (g as usize) < (1 << N)));
+        }
+        Srf04Array {
+            sensors,
+            gpiote,
+            schedule,
+            group: 0,
+            pending: 0,
+            channels: [None; NUM_CHANNELS],
+            readings: [None; N],
+        }
+    }
+
+    /// The GPIOTE peripheral, channels not used by the array may be configured by the app
+    pub fn gpiote(&self) -> &Gpiote {
+        &self.gpiote
+    }
+
+    fn num_groups(&self) -> usize {
+        match self.schedule {
+            Schedule::RoundRobin => N,
+            Schedule::Groups(groups) => groups.len(),
+        }
+    }
+
+    fn group_mask(&self, group: usize) -> u8 {
+        match self.schedule {
+            Schedule::RoundRobin => 1 << group,
+            Schedule::Groups(groups) => groups[group],
+        }
+    }
+
+    /// Closes the current slot and fires the next group
+    ///
+    /// Sensors of the previous group that haven't answered yet are recorded as `Error::NoEcho`.
+    pub fn fire_next(&mut self, now: TimerInstantU32<FREQ>) {
+        for i in 0..N {
+            if self.pending & (1 << i) != 0 {
+                self.sensors[i].cancel();
+                self.readings[i] = Some(Reading {
+                    distance: Err(Error::NoEcho),
+                    at: now,
+                });
+            }
+        }
+
+        let mask = self.group_mask(self.group);
+        self.group = (self.group + 1) % self.num_groups();
+        self.bind(mask);
+        self.pending = mask;
+        for i in 0..N {
+            if mask & (1 << i) != 0 {
+                self.sensors[i].trigger();
+            }
+        }
+    }
+
+    /// Hooks the echo pins of the sensors in `mask` up to the first GPIOTE channels
+    fn bind(&mut self, mask: u8) {
+        let mut sensors = (0..N).filter(|i| mask & (1 << i) != 0);
+        for ch in 0..NUM_CHANNELS {
+            let channel = channel(&self.gpiote, ch);
+            match (self.channels[ch], sensors.next()) {
+                (_, Some(i)) => {
+                    channel
+                        .input_pin(self.sensors[i].echo_pin())
+                        .toggle()
+                        .enable_interrupt();
+                    self.channels[ch] = Some(i);
+                }
+                (Some(old), None) => {
+                    // Channel no longer needed by this group
+                    channel
+                        .input_pin(self.sensors[old].echo_pin())
+                        .none()
+                        .disable_interrupt();
+                    self.channels[ch] = None;
+                }
+                (None, None) => continue,
+            }
+            channel.reset_events();
+        }
+    }
+
+    /// Handles the GPIOTE interrupt
+    pub fn on_gpiote(&mut self, now: TimerInstantU32<FREQ>) {
+        for ch in 0..NUM_CHANNELS {
+            let i = match self.channels[ch] {
+                Some(i) => i,
+                None => continue,
+            };
+            let channel = channel(&self.gpiote, ch);
+            if !channel.is_event_triggered() {
+                continue;
+            }
+            channel.reset_events();
+            if let Some(distance) = self.sensors[i].on_edge(now) {
+                self.pending &= !(1 << i);
+                self.readings[i] = Some(Reading { distance, at: now });
+            }
+        }
+    }
+
+    /// Latest reading of every sensor, `None` until a sensor has been fired once
+    pub fn readings(&self) -> &[Option<Reading<FREQ>>; N] {
+        &self.readings
+    }
+}
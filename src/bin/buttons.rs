@@ -0,0 +1,174 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// All four DK buttons through the GPIOTE PORT event, without using a single GPIOTE channel. Each
+// LED follows its button and the button events are logged. Holding buttons 1 and 2 for 3 s, or
+// pressing 4, 3, 2, 1 in a row, runs a service command.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{fugit::TimerDurationU32, DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Level, Output, Pin, PullUp, PushPull},
+        gpiote::Gpiote,
+        prelude::*,
+    };
+    use nrf_play::{
+        button::{Button, Event, Polling, Timings, POLL_MS},
+        combo::{Command, Matcher, Pattern},
+        port::PortWatch,
+    };
+    const FREQ: u32 = 64_000_000;
+    const COMMANDS: [Command<FREQ>; 2] = [
+        Command {
+            name: "calibrate",
+            pattern: Pattern::Chord {
+                buttons: 0b0011,
+                hold: TimerDurationU32::millis(3000),
+            },
+            action: enter_calibration,
+        },
+        Command {
+            name: "format",
+            pattern: Pattern::Sequence {
+                buttons: &[3, 2, 1, 0],
+                gap: TimerDurationU32::millis(1000),
+            },
+            action: format_sd,
+        },
+    ];
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        watch: PortWatch<PullUp, 4>,
+        polling: Polling,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        buttons: [Button<FREQ>; 4],
+        matcher: Matcher<2, FREQ>,
+        leds: [Pin<Output<PushPull>>; 4],
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let leds = [
+            p0.p0_13.into_push_pull_output(Level::High).degrade(),
+            p0.p0_14.into_push_pull_output(Level::High).degrade(),
+            p0.p0_15.into_push_pull_output(Level::High).degrade(),
+            p0.p0_16.into_push_pull_output(Level::High).degrade(),
+        ];
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        let watch = PortWatch::new(
+            [
+                p0.p0_11.into_pullup_input().degrade(),
+                p0.p0_12.into_pullup_input().degrade(),
+                p0.p0_24.into_pullup_input().degrade(),
+                p0.p0_25.into_pullup_input().degrade(),
+            ],
+            &gpiote,
+        );
+
+        defmt::info!("Press any button!");
+        (
+            Shared {
+                watch,
+                polling: Polling::new(),
+            },
+            Local {
+                gpiote,
+                buttons: [(); 4].map(|_| Button::new(Timings::default())),
+                matcher: Matcher::new(COMMANDS),
+                leds,
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [watch, polling], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        let change = ctx.shared.watch.lock(|w| w.on_port_event());
+        defmt::debug!("Port: {}", change);
+        if ctx.shared.polling.lock(|p| p.start()) {
+            poll_buttons::spawn().ok();
+        }
+    }
+
+    #[task(shared = [watch, polling], local = [buttons, matcher, leds])]
+    fn poll_buttons(mut ctx: poll_buttons::Context) {
+        let now = monotonics::now();
+        let levels = ctx.shared.watch.lock(|w| w.read());
+        let matcher = ctx.local.matcher;
+        for (i, (button, led)) in ctx
+            .local
+            .buttons
+            .iter_mut()
+            .zip(ctx.local.leds.iter_mut())
+            .enumerate()
+        {
+            // Buttons pull the pin low
+            button.update(levels & 1 << i == 0, now);
+            while let Some(event) = button.event() {
+                match event {
+                    Event::Pressed => led.set_low().unwrap(),
+                    Event::Released => led.set_high().unwrap(),
+                    _ => {}
+                }
+                defmt::info!("Button {}: {}", i + 1, event);
+                if let Some(name) = matcher.feed(i as u8, event, now) {
+                    defmt::info!("Command: {=str}", name);
+                }
+            }
+        }
+        if let Some(name) = matcher.tick(now) {
+            defmt::info!("Command: {=str}", name);
+        }
+
+        let buttons = &ctx.local.buttons;
+        let idle = buttons.iter().all(|b| b.is_idle());
+        if (ctx.shared.watch, ctx.shared.polling)
+            .lock(|watch, polling| polling.keep_going(idle && watch.read() == 0b1111))
+        {
+            poll_buttons::spawn_after(POLL_MS.millis()).ok();
+        }
+    }
+
+    fn enter_calibration() {
+        calibrate::spawn().ok();
+    }
+
+    fn format_sd() {
+        format::spawn().ok();
+    }
+
+    // Stand-ins for the real service modes
+    #[task]
+    fn calibrate(_: calibrate::Context) {
+        defmt::warn!("Entering calibration");
+    }
+
+    #[task]
+    fn format(_: format::Context) {
+        defmt::warn!("Formatting the SD card");
+    }
+}
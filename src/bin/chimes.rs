@@ -0,0 +1,140 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Buzzer on P1.10, played by PWM1. Every button press beeps, a click on button 2 plays a jingle,
+// holding button 3 sounds an alarm and a click on button 4 stops it. The alarm can't be cut by
+// the beeps or the jingle.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0, p1, Level, PullUp},
+        gpiote::Gpiote,
+        pac::PWM1,
+    };
+    use nrf_play::{
+        button::{Button, Event, Polling, Timings, POLL_MS},
+        buzzer::Buzzer,
+        melody::{Melody, Sound, Tone},
+        port::PortWatch,
+    };
+    const FREQ: u32 = 64_000_000;
+
+    const UI: u8 = 0;
+    const NOTICE: u8 = 1;
+    const ALARM: u8 = 2;
+    const BEEP: Sound = Sound::Tone(Tone::new(2000, 30));
+    const JINGLE: Sound = Sound::Melody(Melody::new("c5/8 e5/8 g5/8 c6/4. r/8 g5/8 c6/2", 140));
+    const SIREN: Sound = Sound::Melody(Melody::new("a5/8 e5/8 a5/8 e5/8 r/4", 120));
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        watch: PortWatch<PullUp, 4>,
+        polling: Polling,
+        buzzer: Buzzer<PWM1>,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        buttons: [Button<FREQ>; 4],
+    }
+
+    #[init(local = [duty: u16 = 0])]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = p0::Parts::new(ctx.device.P0);
+        let p1 = p1::Parts::new(ctx.device.P1);
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        let watch = PortWatch::new(
+            [
+                p0.p0_11.into_pullup_input().degrade(),
+                p0.p0_12.into_pullup_input().degrade(),
+                p0.p0_24.into_pullup_input().degrade(),
+                p0.p0_25.into_pullup_input().degrade(),
+            ],
+            &gpiote,
+        );
+        let mut buzzer = Buzzer::new(
+            ctx.device.PWM1,
+            p1.p1_10.into_push_pull_output(Level::Low).degrade(),
+            ctx.local.duty,
+        );
+        buzzer.play(UI, JINGLE);
+
+        (
+            Shared {
+                watch,
+                polling: Polling::new(),
+                buzzer,
+            },
+            Local {
+                gpiote,
+                buttons: [(); 4].map(|_| Button::new(Timings::default())),
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(binds = PWM1, priority = 2, shared = [buzzer])]
+    fn on_pwm(mut ctx: on_pwm::Context) {
+        ctx.shared.buzzer.lock(|b| b.on_stopped());
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [watch, polling], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        ctx.shared.watch.lock(|w| w.on_port_event());
+        if ctx.shared.polling.lock(|p| p.start()) {
+            poll_buttons::spawn().ok();
+        }
+    }
+
+    #[task(shared = [watch, polling, buzzer], local = [buttons])]
+    fn poll_buttons(mut ctx: poll_buttons::Context) {
+        let now = monotonics::now();
+        let levels = ctx.shared.watch.lock(|w| w.read());
+        for (i, button) in ctx.local.buttons.iter_mut().enumerate() {
+            // Buttons pull the pin low
+            button.update(levels & 1 << i == 0, now);
+            while let Some(event) = button.event() {
+                let played = ctx.shared.buzzer.lock(|b| match (i, event) {
+                    (_, Event::Pressed) => b.play(UI, BEEP),
+                    (1, Event::Click) => b.play(NOTICE, JINGLE),
+                    (2, Event::LongPress(_)) => b.repeat(ALARM, SIREN),
+                    (3, Event::Click) => {
+                        b.stop(ALARM);
+                        true
+                    }
+                    _ => true,
+                });
+                if !played {
+                    defmt::debug!("Button {}: {} drowned out", i + 1, event);
+                }
+            }
+        }
+
+        let buttons = &ctx.local.buttons;
+        let idle = buttons.iter().all(|b| b.is_idle());
+        if (ctx.shared.watch, ctx.shared.polling)
+            .lock(|watch, polling| polling.keep_going(idle && watch.read() == 0b1111))
+        {
+            poll_buttons::spawn_after(POLL_MS.millis()).ok();
+        }
+    }
+}
@@ -12,18 +12,27 @@ mod app {
         gpiote::Gpiote,
         prelude::*,
     };
+    use nrf_play::{
+        button::{Button, Event, Polling, Timings, POLL_MS},
+        stats::Stats,
+    };
     const FREQ: u32 = 64_000_000;
-    type Instant = <MyMono as rtic::Monotonic>::Instant;
+    // Press durations in ms, 100 ms buckets up to 1 s
+    type PressStats = Stats<10>;
 
     #[monotonic(binds = SysTick, default = true)]
     type MyMono = DwtSystick<FREQ>;
 
     #[shared]
-    struct Shared {}
+    struct Shared {
+        stats: PressStats,
+        polling: Polling,
+    }
 
     #[local]
     struct Local {
         btn: Pin<Input<PullUp>>,
+        button: Button<FREQ>,
         gpiote: Gpiote,
     }
 
@@ -45,25 +54,68 @@ mod app {
             .toggle() // Trigger on both rising and falling edges
             .enable_interrupt();
 
+        summary::spawn_after(30.secs()).ok();
+
         defmt::info!("Press button 1!");
-        (Shared {}, Local { btn, gpiote }, init::Monotonics(mono))
+        (
+            Shared {
+                stats: PressStats::new(0, 100),
+                polling: Polling::new(),
+            },
+            Local {
+                btn,
+                button: Button::new(Timings::default()),
+                gpiote,
+            },
+            init::Monotonics(mono),
+        )
     }
 
-    #[task(binds = GPIOTE, local = [gpiote])]
-    fn on_gpiote(ctx: on_gpiote::Context) {
+    #[task(binds = GPIOTE, shared = [polling], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
         ctx.local.gpiote.reset_events();
-        debounce::spawn_after(30.millis()).ok();
+        if ctx.shared.polling.lock(|p| p.start()) {
+            poll_button::spawn().ok();
+        }
     }
 
-    #[task(local = [btn, pressed_at: Option<Instant> = None])]
-    fn debounce(ctx: debounce::Context) {
-        if ctx.local.btn.is_low().unwrap() {
-            ctx.local.pressed_at.replace(monotonics::now());
-        } else {
-            if let Some(instant) = ctx.local.pressed_at.take() {
-                let t: MillisDurationU32 = (monotonics::now() - instant).convert();
-                defmt::info!("Pressed for {} ms", t.ticks());
+    #[task(shared = [stats, polling], local = [btn, button])]
+    fn poll_button(mut ctx: poll_button::Context) {
+        let button = ctx.local.button;
+        button.update(ctx.local.btn.is_low().unwrap(), monotonics::now());
+        while let Some(event) = button.event() {
+            match event {
+                Event::Pressed => {}
+                Event::Released => {
+                    // Timed between the edges, not when the poll got to see them
+                    let t: MillisDurationU32 =
+                        (button.released_at() - button.pressed_at()).convert();
+                    defmt::info!("Pressed for {} ms", t.ticks());
+                    ctx.shared.stats.lock(|stats| stats.feed(t.ticks()));
+                }
+                event => defmt::info!("{}", event),
             }
         }
+
+        let btn = ctx.local.btn;
+        let idle = button.is_idle();
+        if ctx
+            .shared
+            .polling
+            .lock(|polling| polling.keep_going(idle && btn.is_high().unwrap()))
+        {
+            poll_button::spawn_after(POLL_MS.millis()).ok();
+        }
+    }
+
+    // Presses are few and far between, so the summary covers all of them
+    #[task(shared = [stats])]
+    fn summary(mut ctx: summary::Context) {
+        ctx.shared.stats.lock(|stats| {
+            if stats.count() > 0 {
+                defmt::info!("Presses: {}", stats);
+            }
+        });
+        summary::spawn_after(30.secs()).ok();
     }
 }
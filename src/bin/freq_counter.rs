@@ -0,0 +1,99 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Measures the frequency of the signal on P0.29, from 1 Hz to several MHz. The lower end is set by
+// the longest measurement, a 1 s gate run for 2 s, which has to see two edges.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::DwtSystick;
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Floating, Input, Pin},
+        gpiote::Gpiote,
+        pac::{TIMER1, TIMER2},
+        ppi,
+        prelude::*,
+    };
+    use nrf_play::freq::FreqCounter;
+    const FREQ: u32 = 64_000_000;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {
+        counter: FreqCounter<TIMER1, TIMER2>,
+        // Held on to so the pin stays an input
+        _input: Pin<Input<Floating>>,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        // The HFXO keeps the gate window accurate
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let input = p0.p0_29.into_floating_input().degrade();
+
+        let counter = FreqCounter::new(ctx.device.TIMER1, ctx.device.TIMER2);
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote.channel0().input_pin(&input).lo_to_hi();
+
+        let mut ppi = ppi::Parts::new(ctx.device.PPI);
+        ppi.ppi0.set_event_endpoint(gpiote.channel0().event());
+        ppi.ppi0.set_task_endpoint(counter.task_count());
+        ppi.ppi0.enable();
+        ppi.ppi1.set_event_endpoint(counter.event_window_start());
+        ppi.ppi1.set_task_endpoint(counter.task_capture_start());
+        ppi.ppi1.enable();
+        ppi.ppi2.set_event_endpoint(counter.event_window_end());
+        ppi.ppi2.set_task_endpoint(counter.task_capture_end());
+        ppi.ppi2.enable();
+        ppi.ppi3.set_event_endpoint(counter.event_first_edge());
+        ppi.ppi3.set_task_endpoint(counter.task_capture_first());
+        ppi.ppi3.enable();
+        ppi.ppi4.set_event_endpoint(counter.event_last_edge());
+        ppi.ppi4.set_task_endpoint(counter.task_capture_last());
+        ppi.ppi4.enable();
+
+        measure::spawn().ok();
+
+        (
+            Shared {},
+            Local {
+                counter,
+                _input: input,
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(local = [counter, started: bool = false])]
+    fn measure(ctx: measure::Context) {
+        let counter = ctx.local.counter;
+        if *ctx.local.started {
+            match counter.read() {
+                Some(m) => defmt::info!("{}", m),
+                None => defmt::info!("No signal"),
+            }
+        }
+        counter.start();
+        *ctx.local.started = true;
+        measure::spawn_after(counter.duration().convert()).ok();
+    }
+}
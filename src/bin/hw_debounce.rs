@@ -0,0 +1,120 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Button 1 debounced in hardware: bounces only restart TIMER1 through PPI, the CPU wakes up once
+// the pin has settled and again when a click, long press or repeat is due. LED 1 follows the
+// button.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{
+        fugit::{ExtU32 as _, TimerDurationU32},
+        DwtSystick,
+    };
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Input, Level, Output, Pin, PullUp, PushPull},
+        gpiote::Gpiote,
+        pac::TIMER1,
+        ppi,
+        prelude::*,
+    };
+    use nrf_play::{
+        button::{Button, Event, Timings},
+        debounce::Debouncer,
+    };
+    const FREQ: u32 = 64_000_000;
+    // Quiet time before the level counts
+    const QUIET_MS: u32 = 10;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {
+        debouncer: Debouncer<TIMER1>,
+        btn: Pin<Input<PullUp>>,
+        button: Button<FREQ>,
+        led: Pin<Output<PushPull>>,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let btn = p0.p0_11.into_pullup_input().degrade();
+        let led = p0.p0_13.into_push_pull_output(Level::High).degrade();
+
+        // No interrupt on the edges, they only go to PPI
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote.channel0().input_pin(&btn).toggle();
+
+        let debouncer = Debouncer::new(ctx.device.TIMER1, QUIET_MS.millis());
+        let mut ppi = ppi::Parts::new(ctx.device.PPI);
+        ppi.ppi0.set_event_endpoint(gpiote.channel0().event());
+        ppi.ppi0.set_task_endpoint(debouncer.task_restart());
+        ppi.ppi0.set_fork_task_endpoint(debouncer.task_start());
+        ppi.ppi0.enable();
+
+        let timings = Timings {
+            debounce: TimerDurationU32::from_ticks(0),
+            ..Timings::default()
+        };
+
+        defmt::info!("Press button 1!");
+        (
+            Shared {},
+            Local {
+                debouncer,
+                btn,
+                button: Button::new(timings),
+                led,
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(binds = TIMER1, local = [debouncer])]
+    fn on_settled(ctx: on_settled::Context) {
+        ctx.local.debouncer.on_settled();
+        update_button::spawn().ok();
+    }
+
+    // Runs when the pin has settled and when the button has something due
+    #[task(
+        capacity = 2,
+        local = [btn, button, led, due: Option<update_button::SpawnHandle> = None]
+    )]
+    fn update_button(ctx: update_button::Context) {
+        let button = ctx.local.button;
+        button.update(ctx.local.btn.is_low().unwrap(), monotonics::now());
+        while let Some(event) = button.event() {
+            match event {
+                Event::Pressed => ctx.local.led.set_low().unwrap(),
+                Event::Released => ctx.local.led.set_high().unwrap(),
+                _ => {}
+            }
+            defmt::info!("{}", event);
+        }
+
+        if let Some(handle) = ctx.local.due.take() {
+            handle.cancel().ok();
+        }
+        *ctx.local.due = button
+            .deadline()
+            .and_then(|at| update_button::spawn_at(at).ok());
+    }
+}
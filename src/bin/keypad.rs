@@ -0,0 +1,130 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// 4x4 membrane keypad: rows on P1.01-P1.04, columns on P1.05-P1.08. Logs key presses and releases,
+// and sleeps on the PORT event while no key is down. Time comes from TIMER0, which keeps counting
+// while the CPU sleeps between scans.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p1, Level, OpenDrainConfig::Standard0Disconnect1},
+        gpiote::Gpiote,
+        pac::TIMER0,
+    };
+    use nrf_play::{
+        button::Polling,
+        keypad::Keypad,
+        mono::{fugit::MillisDurationU32, ExtU32, MonoTimer},
+    };
+    // MonoTimer ticks at 1 MHz
+    const FREQ: u32 = 1_000_000;
+    const SCAN_MS: u32 = 5;
+    const LABELS: [[char; 4]; 4] = [
+        ['1', '2', '3', 'A'],
+        ['4', '5', '6', 'B'],
+        ['7', '8', '9', 'C'],
+        ['*', '0', '#', 'D'],
+    ];
+
+    #[monotonic(binds = TIMER0, default = true)]
+    type MyMono = MonoTimer<TIMER0>;
+
+    #[shared]
+    struct Shared {
+        keypad: Keypad<4, 4, FREQ>,
+        polling: Polling,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+    }
+
+    #[init]
+    fn init(ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+        let mono = MonoTimer::new(ctx.device.TIMER0);
+
+        let p1 = p1::Parts::new(ctx.device.P1);
+        let rows = [
+            p1.p1_01
+                .into_open_drain_output(Standard0Disconnect1, Level::High)
+                .degrade(),
+            p1.p1_02
+                .into_open_drain_output(Standard0Disconnect1, Level::High)
+                .degrade(),
+            p1.p1_03
+                .into_open_drain_output(Standard0Disconnect1, Level::High)
+                .degrade(),
+            p1.p1_04
+                .into_open_drain_output(Standard0Disconnect1, Level::High)
+                .degrade(),
+        ];
+        let cols = [
+            p1.p1_05.into_pullup_input().degrade(),
+            p1.p1_06.into_pullup_input().degrade(),
+            p1.p1_07.into_pullup_input().degrade(),
+            p1.p1_08.into_pullup_input().degrade(),
+        ];
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        let keypad = Keypad::new(rows, cols, &gpiote, 20.millis());
+
+        defmt::info!("Press a key!");
+        (
+            Shared {
+                keypad,
+                polling: Polling::new(),
+            },
+            Local { gpiote },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [keypad, polling], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        ctx.shared.keypad.lock(|k| k.on_port_event());
+        if ctx.shared.polling.lock(|p| p.start()) {
+            scan::spawn().ok();
+        }
+    }
+
+    #[task(shared = [keypad, polling], local = [ghosting: bool = false])]
+    fn scan(mut ctx: scan::Context) {
+        let now = monotonics::now();
+        // Stopping under the same lock as the interrupt, a press once sensing is back starts a scan
+        let (more, ghosting) =
+            (&mut ctx.shared.keypad, &mut ctx.shared.polling).lock(|keypad, polling| {
+                let more = polling.keep_going(!keypad.scan(now));
+                (more, keypad.matrix().is_ghosting())
+            });
+        if more {
+            scan::spawn_after(SCAN_MS.millis()).ok();
+        }
+
+        // Logged outside the lock, the interrupt shouldn't wait for RTT
+        while let Some(event) = ctx.shared.keypad.lock(|k| k.event()) {
+            let label = LABELS[event.key.row as usize][event.key.col as usize];
+            let at: MillisDurationU32 = event.at.duration_since_epoch().convert();
+            if event.pressed {
+                defmt::info!("{=char} down at {} ms", label, at.ticks());
+            } else {
+                defmt::info!("{=char} up at {} ms", label, at.ticks());
+            }
+        }
+        if ghosting && !*ctx.local.ghosting {
+            defmt::warn!("Too many keys down to tell which");
+        }
+        *ctx.local.ghosting = ghosting;
+    }
+}
@@ -0,0 +1,130 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Push-encoder on the QDEC: A on P1.01, B on P1.02 and the switch on P1.03, common pin to GND.
+// Turning logs the position, fast turns count more, and a long press sets it back to zero.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p1, PullUp},
+        gpiote::Gpiote,
+    };
+    use nrf_play::{
+        button::{self, Polling, Timings, POLL_MS},
+        port::PortWatch,
+        qdec::{Acceleration, Config, Encoder, Event},
+    };
+    const FREQ: u32 = 64_000_000;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        encoder: Encoder<FREQ>,
+        switch: PortWatch<PullUp, 1>,
+        polling: Polling,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p1 = p1::Parts::new(ctx.device.P1);
+        let config = Config {
+            acceleration: Some(Acceleration {
+                from: 20,
+                max_factor: 10,
+            }),
+            ..Config::default()
+        };
+        let encoder = Encoder::new(
+            ctx.device.QDEC,
+            p1.p1_01.into_pullup_input().degrade(),
+            p1.p1_02.into_pullup_input().degrade(),
+            config,
+        )
+        .with_switch(Timings::default());
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        let switch = PortWatch::new([p1.p1_03.into_pullup_input().degrade()], &gpiote);
+
+        defmt::info!("Turn the knob!");
+        (
+            Shared {
+                encoder,
+                switch,
+                polling: Polling::new(),
+            },
+            Local { gpiote },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(binds = QDEC, priority = 2, shared = [encoder])]
+    fn on_qdec(mut ctx: on_qdec::Context) {
+        let now = monotonics::now();
+        ctx.shared.encoder.lock(|e| e.on_report(now));
+        handle_events::spawn().ok();
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [switch, polling], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        ctx.shared.switch.lock(|s| s.on_port_event());
+        if ctx.shared.polling.lock(|p| p.start()) {
+            poll_switch::spawn().ok();
+        }
+    }
+
+    #[task(shared = [encoder, switch, polling])]
+    fn poll_switch(mut ctx: poll_switch::Context) {
+        let pressed = ctx.shared.switch.lock(|s| s.read() == 0);
+        let now = monotonics::now();
+        ctx.shared.encoder.lock(|e| e.update_switch(pressed, now));
+        handle_events::spawn().ok();
+
+        if (ctx.shared.encoder, ctx.shared.switch, ctx.shared.polling).lock(
+            |encoder, switch, polling| {
+                polling.keep_going(encoder.is_switch_idle() && switch.read() != 0)
+            },
+        ) {
+            poll_switch::spawn_after(POLL_MS.millis()).ok();
+        }
+    }
+
+    #[task(shared = [encoder])]
+    fn handle_events(mut ctx: handle_events::Context) {
+        ctx.shared.encoder.lock(|encoder| {
+            while let Some(event) = encoder.event() {
+                match event {
+                    Event::Turn(delta) => {
+                        defmt::info!("Position {} ({})", encoder.position(), delta)
+                    }
+                    Event::Button(button::Event::LongPress(_)) => {
+                        encoder.set_position(0);
+                        defmt::info!("Position reset");
+                    }
+                    Event::Button(event) => defmt::debug!("Switch: {}", event),
+                }
+            }
+        });
+    }
+}
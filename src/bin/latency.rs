@@ -0,0 +1,119 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Measures the delay from a TX pulse on P0.13 to the RX rising edge on P0.11, wired up like
+// `tx_rx` but timed by TIMER0 at 16 MHz. Put the cable, level shifter or optocoupler under test
+// between the two pins.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::DwtSystick;
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Level},
+        gpiote::Gpiote,
+        pac::TIMER0,
+        ppi,
+        prelude::*,
+    };
+    use nrf_play::{
+        latency::{nanos, Duration, LatencyMeter},
+        stats::Stats,
+    };
+    const FREQ: u32 = 64_000_000;
+    // Runs per summary
+    const RUNS: u32 = 1_000;
+    // Delays in ns, 100 ns buckets up to 1.6 us
+    type DelayStats = Stats<16>;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {}
+
+    #[local]
+    struct Local {
+        meter: LatencyMeter<TIMER0>,
+        stats: DelayStats,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let rx_pin = p0.p0_11.into_pulldown_input().degrade();
+        let tx_pin = p0.p0_13.into_push_pull_output(Level::Low).degrade();
+
+        // 10 us pulses, runs of 100 us
+        let mut meter = LatencyMeter::new(
+            ctx.device.TIMER0,
+            Duration::from_ticks(160),
+            Duration::from_ticks(1_600),
+        );
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote.channel0().output_pin(tx_pin).init_low();
+        gpiote.channel1().input_pin(&rx_pin).lo_to_hi();
+
+        let mut ppi = ppi::Parts::new(ctx.device.PPI);
+        ppi.ppi0.set_event_endpoint(meter.event_tx_start());
+        ppi.ppi0.set_task_endpoint(gpiote.channel0().task_set());
+        ppi.ppi0.enable();
+        ppi.ppi1.set_event_endpoint(meter.event_tx_end());
+        ppi.ppi1.set_task_endpoint(gpiote.channel0().task_clr());
+        ppi.ppi1.enable();
+        ppi.ppi2.set_event_endpoint(gpiote.channel1().event());
+        ppi.ppi2.set_task_endpoint(meter.task_capture_rx());
+        ppi.ppi2.enable();
+
+        meter.start();
+
+        (
+            Shared {},
+            Local {
+                meter,
+                stats: DelayStats::new(0, 100),
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(binds = TIMER0, local = [meter, stats, runs: u32 = 0])]
+    fn on_timer0(ctx: on_timer0::Context) {
+        let stats = ctx.local.stats;
+        if let Some(d) = ctx.local.meter.on_done() {
+            stats.feed(nanos(d));
+        }
+        *ctx.local.runs += 1;
+
+        if *ctx.local.runs == RUNS {
+            match (stats.min(), stats.mean(), stats.max()) {
+                (Some(min), Some(mean), Some(max)) => defmt::info!(
+                    "min {} ns, mean {} ns, max {} ns, jitter {} ns rms / {} ns p-p, {} timeouts",
+                    min,
+                    mean,
+                    max,
+                    stats.std_dev().unwrap_or(0),
+                    max - min,
+                    RUNS - stats.count()
+                ),
+                _ => defmt::info!("No RX edges, check the wiring"),
+            }
+            stats.reset();
+            *ctx.local.runs = 0;
+        }
+        ctx.local.meter.start();
+    }
+}
@@ -0,0 +1,82 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Background patterns on all four LEDs, played by PWM0 without the CPU. Every 10 s an error code
+// takes over LED 1 at a higher priority for a few seconds.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use nrf52840_hal::{
+        gpio::{p0::Parts, Level},
+        pac::{PWM0, TIMER0},
+    };
+    use nrf_play::{
+        leds::{Frames, Leds},
+        mono::{ExtU32, MonoTimer},
+        pattern::Pattern,
+    };
+    const BACKGROUND: u8 = 0;
+    const ERROR: u8 = 1;
+
+    #[monotonic(binds = TIMER0, default = true)]
+    type MyMono = MonoTimer<TIMER0>;
+
+    #[shared]
+    struct Shared {
+        leds: Leds<PWM0>,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init(local = [frames: Frames = [[[0; 4]; nrf_play::pattern::MAX_STEPS]; 2]])]
+    fn init(ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let mono = MonoTimer::new(ctx.device.TIMER0);
+        let p0 = Parts::new(ctx.device.P0);
+        let pins = [
+            p0.p0_13.into_push_pull_output(Level::High).degrade(),
+            p0.p0_14.into_push_pull_output(Level::High).degrade(),
+            p0.p0_15.into_push_pull_output(Level::High).degrade(),
+            p0.p0_16.into_push_pull_output(Level::High).degrade(),
+        ];
+        let mut leds = Leds::new(ctx.device.PWM0, pins, ctx.local.frames);
+        leds.set(0, BACKGROUND, Pattern::Heartbeat { bpm: 60 });
+        leds.set(1, BACKGROUND, Pattern::Breathe { period_ms: 2000 });
+        leds.set(
+            2,
+            BACKGROUND,
+            Pattern::Blink {
+                on_ms: 100,
+                off_ms: 900,
+            },
+        );
+        leds.set(3, BACKGROUND, Pattern::On(32));
+
+        error::spawn_after(10.secs()).ok();
+        (Shared { leds }, Local {}, init::Monotonics(mono))
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+
+    #[task(shared = [leds])]
+    fn error(mut ctx: error::Context) {
+        defmt::info!("Error 23");
+        ctx.shared
+            .leds
+            .lock(|leds| leds.set(0, ERROR, Pattern::Code(23)));
+        recover::spawn_after(10.secs()).ok();
+    }
+
+    #[task(shared = [leds])]
+    fn recover(mut ctx: recover::Context) {
+        defmt::info!("Recovered");
+        ctx.shared.leds.lock(|leds| leds.clear(0, ERROR));
+        error::spawn_after(10.secs()).ok();
+    }
+}
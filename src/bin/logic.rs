@@ -0,0 +1,104 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Logic analyzer on the SRF04, I2C and a few spare pins
+//
+// Convert the output to a VCD file for GTKWave with the host tool:
+//   cargo rb logic > capture.txt
+//   cargo la2vcd capture.txt > capture.vcd
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{clocks::Clocks, gpio::p0::Parts, gpiote::Gpiote};
+    use nrf_play::logic::{self, LogicCapture};
+    const FREQ: u32 = 64_000_000;
+    const PINS: usize = 8;
+    // Well inside the 67 s the timestamps take to wrap
+    const HEARTBEAT: u32 = 10 * FREQ;
+    const NAMES: [&str; PINS] = [
+        "trig", "echo", "scl", "sda", "p0_28", "p0_29", "p0_30", "p0_31",
+    ];
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        capture: LogicCapture<PINS>,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mut mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        // Floating inputs so the probed circuit isn't loaded
+        let p0 = Parts::new(ctx.device.P0);
+        let pins = [
+            p0.p0_03.into_floating_input().degrade(),
+            p0.p0_04.into_floating_input().degrade(),
+            p0.p0_27.into_floating_input().degrade(),
+            p0.p0_26.into_floating_input().degrade(),
+            p0.p0_28.into_floating_input().degrade(),
+            p0.p0_29.into_floating_input().degrade(),
+            p0.p0_30.into_floating_input().degrade(),
+            p0.p0_31.into_floating_input().degrade(),
+        ];
+        let capture = LogicCapture::new(pins, Gpiote::new(ctx.device.GPIOTE));
+
+        let now = rtic::Monotonic::now(&mut mono);
+        logic::print_header(FREQ, &NAMES, now.ticks(), capture.levels());
+        stream::spawn(now.ticks()).ok();
+
+        (Shared { capture }, Local {}, init::Monotonics(mono))
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [capture])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        let now = monotonics::now();
+        ctx.shared.capture.lock(|c| c.on_gpiote(now.ticks()));
+    }
+
+    // `printed` is the last timestamp sent to the host
+    #[task(shared = [capture])]
+    fn stream(mut ctx: stream::Context, mut printed: u32) {
+        let dropped = ctx.shared.capture.lock(|c| c.take_dropped());
+        if dropped > 0 {
+            logic::print_dropped(dropped);
+        }
+        loop {
+            // The time is taken with the interrupt locked out, no sample can be older
+            match ctx
+                .shared
+                .capture
+                .lock(|c| c.pop().ok_or_else(|| monotonics::now().ticks()))
+            {
+                Ok(sample) => {
+                    logic::print_sample(sample);
+                    printed = sample.at;
+                }
+                Err(now) => {
+                    if now.wrapping_sub(printed) >= HEARTBEAT {
+                        logic::print_heartbeat(now);
+                        printed = now;
+                    }
+                    break;
+                }
+            }
+        }
+        stream::spawn_after(10.millis(), printed).ok();
+    }
+}
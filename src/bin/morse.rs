@@ -0,0 +1,137 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Morse key on button 1. Decoded characters are logged as soon as the gap after them is long
+// enough, and the speed estimate with every word.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{fugit::MillisDurationU32, DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Input, Pin, PullUp},
+        gpiote::Gpiote,
+        prelude::*,
+    };
+    use nrf_play::{
+        button::{Button, Event, Polling, Timings, POLL_MS},
+        morse::Decoder,
+    };
+    const FREQ: u32 = 64_000_000;
+    // Starting speed guess
+    const WPM: u32 = 12;
+    type Instant = <MyMono as rtic::Monotonic>::Instant;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        polling: Polling,
+    }
+
+    #[local]
+    struct Local {
+        btn: Pin<Input<PullUp>>,
+        button: Button<FREQ>,
+        decoder: Decoder,
+        gpiote: Gpiote,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let btn = p0.p0_11.into_pullup_input().degrade();
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote
+            .channel0()
+            .input_pin(&btn)
+            .toggle()
+            .enable_interrupt();
+
+        // Only presses and releases matter, clicks would just wait for nothing
+        let timings = Timings {
+            double_click: None,
+            repeat_delay: None,
+            ..Timings::default()
+        };
+
+        defmt::info!("Key some morse on button 1, starting at {} WPM", WPM);
+        (
+            Shared {
+                polling: Polling::new(),
+            },
+            Local {
+                btn,
+                button: Button::new(timings),
+                decoder: Decoder::new(WPM),
+                gpiote,
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[task(binds = GPIOTE, shared = [polling], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        if ctx.shared.polling.lock(|p| p.start()) {
+            poll::spawn().ok();
+        }
+    }
+
+    #[task(shared = [polling], local = [btn, button, decoder, released_at: Option<Instant> = None])]
+    fn poll(mut ctx: poll::Context) {
+        let (button, decoder) = (ctx.local.button, ctx.local.decoder);
+        let now = monotonics::now();
+        let ms = |from: Instant, to: Instant| -> u32 {
+            let d: MillisDurationU32 = (to - from).convert();
+            d.ticks()
+        };
+
+        button.update(ctx.local.btn.is_low().unwrap(), now);
+        while let Some(event) = button.event() {
+            match event {
+                // Elements and gaps are timed between the edges, the poll sees them late
+                Event::Pressed => {
+                    if let Some(released) = ctx.local.released_at.take() {
+                        decoder.silence(ms(released, button.pressed_at()));
+                    }
+                }
+                Event::Released => {
+                    let element = decoder.press(ms(button.pressed_at(), button.released_at()));
+                    defmt::debug!("{}", element);
+                    ctx.local.released_at.replace(button.released_at());
+                }
+                _ => {}
+            }
+        }
+        if let Some(released) = *ctx.local.released_at {
+            decoder.silence(ms(released, now));
+        }
+        while let Some(c) = decoder.next_char() {
+            if c == ' ' {
+                defmt::info!("(space, {} WPM)", decoder.wpm());
+            } else {
+                defmt::info!("{=char}", c);
+            }
+        }
+
+        let btn = ctx.local.btn;
+        let idle = button.is_idle() && decoder.is_idle();
+        if ctx
+            .shared
+            .polling
+            .lock(|polling| polling.keep_going(idle && btn.is_high().unwrap()))
+        {
+            poll::spawn_after(POLL_MS.millis()).ok();
+        }
+    }
+}
@@ -0,0 +1,300 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Parking assistant
+//
+// The four DK LEDs form a bar that fills up as the obstacle gets closer, and a piezo on P0.28
+// beeps faster the closer it gets, turning into a continuous tone at the stop distance. Button 1
+// mutes/unmutes the buzzer. Without any movement for a while the display goes dark and the sensor
+// is only pinged once a second, until something moves or the button is pressed.
+//
+// The CPU sleeps between events, so time comes from TIMER0, which keeps counting while it does,
+// rather than from the cycle counter and SysTick, which stop.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1, UARTE0_UART0])]
+mod app {
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Input, Level, Output, Pin, PullUp, PushPull},
+        gpiote::Gpiote,
+        pac::{PWM0, TIMER0},
+        prelude::*,
+        pwm::{Channel, Pwm},
+    };
+    use nrf_play::{
+        distance::Distance,
+        mono::{
+            fugit::{MillisDurationU32, TimerInstantU32},
+            ExtU32, MonoTimer,
+        },
+        srf04::Srf04,
+        zone::Zones,
+    };
+    // MonoTimer ticks at 1 MHz
+    const FREQ: u32 = 1_000_000;
+    type Instant = TimerInstantU32<FREQ>;
+
+    // LED bar: one LED lights up below each of these
+    const BAR: [Distance; 4] = [
+        Distance::from_cm(30),
+        Distance::from_cm(60),
+        Distance::from_cm(100),
+        Distance::from_cm(150),
+    ];
+    const BAR_HYSTERESIS: Distance = Distance::from_cm(3);
+    // Beeping starts at `BEEP_FROM`, speeds up from `SLOWEST_MS` to `FASTEST_MS` between beeps
+    // and becomes a continuous tone at `STOP_AT`
+    const BEEP_FROM: Distance = Distance::from_cm(150);
+    const STOP_AT: Distance = Distance::from_cm(30);
+    const SLOWEST_MS: u32 = 800;
+    const FASTEST_MS: u32 = 80;
+    const BEEP_MS: u32 = 50;
+    const TONE_HZ: u32 = 2_000;
+    // Go to sleep after a minute without the distance changing more than `MOTION`
+    const SLEEP_AFTER_MS: u32 = 60_000;
+    const MOTION: Distance = Distance::from_cm(5);
+
+    #[monotonic(binds = TIMER0, default = true)]
+    type MyMono = MonoTimer<TIMER0>;
+
+    #[shared]
+    struct Shared {
+        sensor: Srf04<FREQ>,
+        // Latest reading, `None` when nothing is in range
+        distance: Option<Distance>,
+        muted: bool,
+        asleep: bool,
+        // Reading and time of the last movement
+        moved: Option<(Option<Distance>, Instant)>,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        btn: Pin<Input<PullUp>>,
+        leds: [Pin<Output<PushPull>>; 4],
+        zones: Zones<4, FREQ>,
+        buzzer: Pwm<PWM0>,
+    }
+
+    #[init]
+    fn init(ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+        let mono = MonoTimer::new(ctx.device.TIMER0);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let sensor = Srf04::new(
+            p0.p0_03.into_push_pull_output(Level::Low).degrade(),
+            p0.p0_04.into_pulldown_input().degrade(),
+        );
+        let btn = p0.p0_11.into_pullup_input().degrade();
+        // DK LEDs are active low
+        let leds = [
+            p0.p0_13.into_push_pull_output(Level::High).degrade(),
+            p0.p0_14.into_push_pull_output(Level::High).degrade(),
+            p0.p0_15.into_push_pull_output(Level::High).degrade(),
+            p0.p0_16.into_push_pull_output(Level::High).degrade(),
+        ];
+
+        let buzzer = Pwm::new(ctx.device.PWM0);
+        buzzer
+            .set_output_pin(
+                Channel::C0,
+                p0.p0_28.into_push_pull_output(Level::Low).degrade(),
+            )
+            .set_period(TONE_HZ.hz());
+        buzzer.set_duty_on_common(buzzer.max_duty() / 2);
+        buzzer.disable();
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote
+            .channel0()
+            .input_pin(sensor.echo_pin())
+            .toggle() // Trigger on both rising and falling edges
+            .enable_interrupt();
+        gpiote
+            .channel1()
+            .input_pin(&btn)
+            .hi_to_lo()
+            .enable_interrupt();
+
+        let zones = Zones::new(BAR, BAR_HYSTERESIS, 100.millis());
+
+        send_wave::spawn().ok();
+        beep::spawn().ok();
+
+        (
+            Shared {
+                sensor,
+                distance: None,
+                muted: false,
+                asleep: false,
+                moved: None,
+            },
+            Local {
+                gpiote,
+                btn,
+                leds,
+                zones,
+                buzzer,
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {
+            cortex_m::asm::wfi();
+        }
+    }
+
+    #[task(shared = [sensor, asleep])]
+    fn send_wave(mut ctx: send_wave::Context) {
+        ctx.shared.sensor.lock(|s| s.trigger());
+        let asleep = ctx.shared.asleep.lock(|a| *a);
+        let period = if asleep { 1.secs() } else { 100.millis() };
+        send_wave::spawn_after(period).ok();
+    }
+
+    #[task(binds = GPIOTE, priority = 3, shared = [sensor], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        let now = monotonics::now();
+        let gpiote = ctx.local.gpiote;
+        if gpiote.channel0().is_event_triggered() {
+            gpiote.channel0().reset_events();
+            match ctx.shared.sensor.lock(|s| s.on_edge(now)) {
+                Some(Ok(d)) => reading::spawn(Some(d), now).ok(),
+                Some(Err(_)) => reading::spawn(None, now).ok(),
+                None => None,
+            };
+        }
+        if gpiote.channel1().is_event_triggered() {
+            gpiote.channel1().reset_events();
+            debounce::spawn_after(30.millis()).ok();
+        }
+    }
+
+    #[task(priority = 2, capacity = 2, shared = [distance, asleep, moved], local = [leds, zones])]
+    fn reading(mut ctx: reading::Context, distance: Option<Distance>, now: Instant) {
+        ctx.shared.distance.lock(|d| *d = distance);
+
+        // Anything appearing, disappearing or moving counts as activity
+        let (moved, idle) = ctx.shared.moved.lock(|last| {
+            let moved = match (*last, distance) {
+                (Some((Some(from), _)), Some(d)) => d.abs_diff(from) > MOTION,
+                (Some((from, _)), d) => from.is_some() != d.is_some(),
+                (None, _) => true,
+            };
+            if moved {
+                last.replace((distance, now));
+            }
+            (moved, now - last.map_or(now, |(_, at)| at))
+        });
+        let idle: MillisDurationU32 = idle.convert();
+
+        let asleep = ctx.shared.asleep.lock(|asleep| {
+            if moved && *asleep {
+                defmt::info!("Waking up");
+                *asleep = false;
+            } else if !*asleep && idle.ticks() > SLEEP_AFTER_MS {
+                defmt::info!("Nothing moved for a while, going to sleep");
+                *asleep = true;
+            }
+            *asleep
+        });
+
+        // Beyond the sensor's range counts as the farthest zone
+        let zone = match distance {
+            Some(d) => {
+                ctx.local.zones.update(d, now);
+                ctx.local.zones.zone()
+            }
+            None => BAR.len(),
+        };
+        let lit = if asleep { 0 } else { BAR.len() - zone };
+        for (i, led) in ctx.local.leds.iter_mut().enumerate() {
+            if i < lit {
+                led.set_low().ok();
+            } else {
+                led.set_high().ok();
+            }
+        }
+    }
+
+    #[task(shared = [muted, asleep, moved], local = [btn])]
+    fn debounce(mut ctx: debounce::Context) {
+        if ctx.local.btn.is_low().unwrap() {
+            // A press always wakes the assistant up, and toggles mute if it was already awake
+            let was_asleep = ctx.shared.asleep.lock(|a| core::mem::replace(a, false));
+            if was_asleep {
+                defmt::info!("Waking up");
+                // Start the inactivity timeout over
+                ctx.shared.moved.lock(|m| *m = None);
+            } else {
+                let muted = ctx.shared.muted.lock(|m| {
+                    *m = !*m;
+                    *m
+                });
+                defmt::info!("Muted: {}", muted);
+            }
+        }
+    }
+
+    #[task(shared = [distance, muted, asleep], local = [buzzer, on: bool = false])]
+    fn beep(ctx: beep::Context) {
+        let beep::SharedResources {
+            distance,
+            muted,
+            asleep,
+        } = ctx.shared;
+        let interval = (distance, muted, asleep).lock(|distance, muted, asleep| {
+            if *muted || *asleep {
+                None
+            } else {
+                distance.and_then(beep_interval)
+            }
+        });
+
+        let next_ms = match interval {
+            // Continuous tone
+            Some(0) => {
+                *ctx.local.on = true;
+                100
+            }
+            Some(_) if !*ctx.local.on => {
+                *ctx.local.on = true;
+                BEEP_MS
+            }
+            Some(ms) => {
+                *ctx.local.on = false;
+                ms
+            }
+            None => {
+                *ctx.local.on = false;
+                100
+            }
+        };
+        let buzzer: &Pwm<PWM0> = ctx.local.buzzer;
+        if *ctx.local.on {
+            buzzer.enable();
+        } else {
+            buzzer.disable();
+        }
+        beep::spawn_after(next_ms.millis()).ok();
+    }
+
+    /// Silence between beeps in ms, 0 for a continuous tone, `None` when too far away to beep
+    fn beep_interval(d: Distance) -> Option<u32> {
+        if d >= BEEP_FROM {
+            None
+        } else if d <= STOP_AT {
+            Some(0)
+        } else {
+            let span = (BEEP_FROM - STOP_AT).mm();
+            let offset = (d - STOP_AT).mm();
+            Some(FASTEST_MS + (SLOWEST_MS - FASTEST_MS) * offset / span)
+        }
+    }
+}
@@ -0,0 +1,70 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{clocks::Clocks, gpio::p0::Parts, gpiote::Gpiote};
+    use nrf_play::srf04::Srf04;
+    const FREQ: u32 = 64_000_000;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        sensor: Srf04<FREQ>,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        // Parallax PING / SRF05 in mode 2: trigger and echo on the same pin
+        let p0 = Parts::new(ctx.device.P0);
+        let sensor = Srf04::single_pin(p0.p0_03.into_pulldown_input().degrade());
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote
+            .channel0()
+            .input_pin(sensor.echo_pin())
+            .toggle() // Trigger on both rising and falling edges
+            .enable_interrupt();
+
+        send_wave::spawn().ok();
+
+        (Shared { sensor }, Local { gpiote }, init::Monotonics(mono))
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(shared = [sensor])]
+    fn send_wave(mut ctx: send_wave::Context) {
+        ctx.shared.sensor.lock(|s| s.trigger());
+        send_wave::spawn_after(100.millis()).ok();
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [sensor], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        match ctx.shared.sensor.lock(|s| s.on_edge(monotonics::now())) {
+            Some(Ok(d)) => defmt::info!("Distance: {}", d),
+            Some(Err(e)) => defmt::info!("Error: {}", e),
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,113 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Measures the signal on P0.29 twice a second. PWM0 puts a 1 kHz, 25% test signal on P0.30,
+// jumper the two pins to try it out.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Input, Level, Pin, PullDown},
+        gpiote::Gpiote,
+        pac::{PWM0, TIMER0},
+        ppi,
+        prelude::*,
+        pwm::{Channel, Pwm},
+    };
+    use nrf_play::pwm_in::{Mode, Pulse, PwmInput};
+    const FREQ: u32 = 64_000_000;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        input: PwmInput<TIMER0>,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        _pin: Pin<Input<PullDown>>,
+        _pwm: Pwm<PWM0>,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let pin = p0.p0_29.into_pulldown_input().degrade();
+
+        let pwm = Pwm::new(ctx.device.PWM0);
+        pwm.set_output_pin(
+            Channel::C0,
+            p0.p0_30.into_push_pull_output(Level::Low).degrade(),
+        )
+        .set_period(1_000.hz());
+        pwm.set_duty_on_common(pwm.max_duty() / 4);
+
+        // Switch to `Mode::Continuous` to get every single period
+        let mut input = PwmInput::new(ctx.device.TIMER0, Mode::OneShot);
+        input.set_callback(|p| {
+            report::spawn(p).ok();
+        });
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote
+            .channel0()
+            .input_pin(&pin)
+            .lo_to_hi()
+            .enable_interrupt();
+        gpiote.channel1().input_pin(&pin).hi_to_lo();
+
+        let mut ppi = ppi::Parts::new(ctx.device.PPI);
+        ppi.ppi0.set_event_endpoint(gpiote.channel0().event());
+        ppi.ppi0.set_task_endpoint(input.task_capture_rise());
+        ppi.ppi0.enable();
+        ppi.ppi1.set_event_endpoint(gpiote.channel1().event());
+        ppi.ppi1.set_task_endpoint(input.task_capture_fall());
+        ppi.ppi1.enable();
+
+        measure::spawn().ok();
+
+        (
+            Shared { input },
+            Local {
+                gpiote,
+                _pin: pin,
+                _pwm: pwm,
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(shared = [input])]
+    fn measure(mut ctx: measure::Context) {
+        ctx.shared.input.lock(|i| i.arm());
+        measure::spawn_after(500.millis()).ok();
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [input], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        ctx.shared.input.lock(|i| i.on_rise());
+    }
+
+    #[task]
+    fn report(_: report::Context, pulse: Pulse) {
+        defmt::info!("{} Hz, {}", pulse.frequency_hz(), pulse);
+    }
+}
@@ -0,0 +1,186 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Measures once per button press with any `RangeSensor`. The SRF04 on P0.03/P0.04 by default, a
+// VL53L0X or VL53L1X on TWIM0 (SCL P0.27, SDA P0.26) with the `vl53l0x` or `vl53l1x` feature:
+//
+//     cargo rb range_button --features vl53l1x
+//
+// Only the sensor's type and construction differ, plus the echo edges the SRF04 needs timed.
+#[cfg(all(feature = "vl53l0x", feature = "vl53l1x"))]
+compile_error!("pick one of the `vl53l0x` and `vl53l1x` features");
+
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Input, Pin, PullUp},
+        gpiote::Gpiote,
+        prelude::*,
+    };
+    use nrf_play::{
+        button::{Button, Event, Polling, Timings, POLL_MS},
+        range::RangeSensor,
+    };
+    #[cfg(not(any(feature = "vl53l0x", feature = "vl53l1x")))]
+    use {nrf52840_hal::gpio::Level, nrf_play::srf04::Srf04};
+    #[cfg(any(feature = "vl53l0x", feature = "vl53l1x"))]
+    use {
+        nrf52840_hal::{
+            pac::TWIM0,
+            twim::{self, Twim},
+        },
+        nrf_play::vl53l0x::DEFAULT_ADDRESS,
+    };
+    const FREQ: u32 = 64_000_000;
+    // Give up on a measurement after 20 polls, 100 ms
+    const MAX_POLLS: u32 = 20;
+
+    #[cfg(not(any(feature = "vl53l0x", feature = "vl53l1x")))]
+    type Sensor = Srf04<FREQ>;
+    #[cfg(feature = "vl53l0x")]
+    type Sensor = nrf_play::vl53l0x::Vl53l0x<Twim<TWIM0>>;
+    #[cfg(feature = "vl53l1x")]
+    type Sensor = nrf_play::vl53l1x::Vl53l1x<Twim<TWIM0>>;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        sensor: Sensor,
+        polling: Polling,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        btn: Pin<Input<PullUp>>,
+        button: Button<FREQ>,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let btn = p0.p0_11.into_pullup_input().degrade();
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote
+            .channel1()
+            .input_pin(&btn)
+            .toggle()
+            .enable_interrupt();
+
+        #[cfg(not(any(feature = "vl53l0x", feature = "vl53l1x")))]
+        let sensor = {
+            let sensor = Srf04::new(
+                p0.p0_03.into_push_pull_output(Level::Low).degrade(),
+                p0.p0_04.into_pulldown_input().degrade(),
+            );
+            gpiote
+                .channel0()
+                .input_pin(sensor.echo_pin())
+                .toggle()
+                .enable_interrupt();
+            sensor
+        };
+        #[cfg(any(feature = "vl53l0x", feature = "vl53l1x"))]
+        let sensor = {
+            let twim = Twim::new(
+                ctx.device.TWIM0,
+                twim::Pins {
+                    scl: p0.p0_27.into_floating_input().degrade(),
+                    sda: p0.p0_26.into_floating_input().degrade(),
+                },
+                twim::Frequency::K400,
+            );
+            match Sensor::new(twim, DEFAULT_ADDRESS) {
+                Ok(sensor) => sensor,
+                Err(e) => defmt::panic!("Sensor init failed: {}", e),
+            }
+        };
+
+        (
+            Shared {
+                sensor,
+                polling: Polling::new(),
+            },
+            Local {
+                gpiote,
+                btn,
+                button: Button::new(Timings::default()),
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [sensor, polling], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        let gpiote = ctx.local.gpiote;
+        // Echo edges, timed as soon as possible
+        #[cfg(not(any(feature = "vl53l0x", feature = "vl53l1x")))]
+        if gpiote.channel0().is_event_triggered() {
+            gpiote.channel0().reset_events();
+            let now = monotonics::now();
+            ctx.shared.sensor.lock(|s| s.on_edge(now));
+        }
+        if gpiote.channel1().is_event_triggered() {
+            gpiote.channel1().reset_events();
+            if ctx.shared.polling.lock(|p| p.start()) {
+                poll_button::spawn().ok();
+            }
+        }
+    }
+
+    #[task(shared = [sensor, polling], local = [btn, button])]
+    fn poll_button(mut ctx: poll_button::Context) {
+        let button = ctx.local.button;
+        button.update(ctx.local.btn.is_low().unwrap(), monotonics::now());
+        while let Some(event) = button.event() {
+            if event == Event::Pressed {
+                // Button is pressed - start a measurement
+                match ctx.shared.sensor.lock(|s| s.start()) {
+                    Ok(()) => {
+                        poll::spawn_after(5.millis(), 0).ok();
+                    }
+                    Err(e) => defmt::info!("Error: {}", e),
+                }
+            }
+        }
+
+        let btn = ctx.local.btn;
+        let idle = button.is_idle();
+        if ctx
+            .shared
+            .polling
+            .lock(|polling| polling.keep_going(idle && btn.is_high().unwrap()))
+        {
+            poll_button::spawn_after(POLL_MS.millis()).ok();
+        }
+    }
+
+    #[task(shared = [sensor])]
+    fn poll(mut ctx: poll::Context, attempt: u32) {
+        match ctx.shared.sensor.lock(|s| s.read()) {
+            Ok(d) => defmt::info!("Distance: {}", d),
+            Err(nb::Error::Other(e)) => defmt::info!("Error: {}", e),
+            Err(nb::Error::WouldBlock) if attempt < MAX_POLLS => {
+                poll::spawn_after(5.millis(), attempt + 1).ok();
+            }
+            Err(nb::Error::WouldBlock) => defmt::info!("Timeout"),
+        }
+    }
+}
@@ -5,28 +5,40 @@ use nrf_play as _; // global logger + panicking-behavior + memory layout
 
 #[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
 mod app {
-    use dwt_systick_monotonic::{
-        fugit::{MicrosDurationU32, TimerInstantU32},
-        DwtSystick, ExtU32,
-    };
+    use dwt_systick_monotonic::{fugit::TimerInstantU32, DwtSystick, ExtU32};
     use nrf52840_hal::{
         clocks::Clocks,
         gpio::{p0::Parts, Input, Level, Output, Pin, PullDown, PushPull},
         gpiote::Gpiote,
         prelude::*,
     };
+    use nrf_play::{
+        calibration::Calibration,
+        distance::Distance,
+        filter::{Chain, Ema, Filter, Median, OutlierReject},
+        stats::Stats,
+    };
     const FREQ: u32 = 64_000_000;
 
+    // Drop readings more than 50 cm off, then smooth what's left
+    type EchoFilter = Chain<Chain<OutlierReject, Median<5>>, Ema<2>>;
+    // Distances in mm, 50 cm buckets up to 4 m
+    type DistanceStats = Stats<8>;
+
     #[monotonic(binds = SysTick, default = true)]
     type MyMono = DwtSystick<FREQ>;
 
     #[shared]
-    struct Shared {}
+    struct Shared {
+        stats: DistanceStats,
+    }
     #[local]
     struct Local {
         echo_pin: Pin<Input<PullDown>>,
         trig_pin: Pin<Output<PushPull>>,
         gpiote: Gpiote,
+        filter: EchoFilter,
+        calibration: Calibration,
     }
 
     #[init]
@@ -48,14 +60,23 @@ mod app {
             .toggle() // Trigger on both rising and falling edges
             .enable_interrupt();
 
+        let filter = OutlierReject::new(Distance::from_cm(50).um(), 3)
+            .chain(Median::new())
+            .chain(Ema::new());
+
         send_wave::spawn().ok();
+        summary::spawn_after(10.secs()).ok();
 
         (
-            Shared {},
+            Shared {
+                stats: DistanceStats::new(0, 500),
+            },
             Local {
                 echo_pin,
                 trig_pin,
                 gpiote,
+                filter,
+                calibration: Calibration::load().unwrap_or_default(),
             },
             init::Monotonics(mono),
         )
@@ -74,8 +95,17 @@ mod app {
         send_wave::spawn_after(100.millis()).ok();
     }
 
-    #[task(binds = GPIOTE, local = [gpiote, echo_pin, start: Option<TimerInstantU32<FREQ>> = None])]
-    fn on_gpiote(ctx: on_gpiote::Context) {
+    #[task(shared = [stats])]
+    fn summary(mut ctx: summary::Context) {
+        ctx.shared.stats.lock(|stats| {
+            defmt::info!("Last 10 s: {}", stats);
+            stats.reset();
+        });
+        summary::spawn_after(10.secs()).ok();
+    }
+
+    #[task(binds = GPIOTE, shared = [stats], local = [gpiote, echo_pin, filter, calibration, start: Option<TimerInstantU32<FREQ>> = None])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
         ctx.local.gpiote.reset_events();
         if ctx.local.echo_pin.is_high().unwrap() {
             // Echo pulse started - store start time
@@ -83,8 +113,12 @@ mod app {
         } else {
             // Echo pulse ended - calculate pulse duration
             if let Some(instant) = ctx.local.start.take() {
-                let t: MicrosDurationU32 = (monotonics::now() - instant).convert();
-                defmt::info!("Distance: {} cm", t.ticks() as f32 / 58.0);
+                let d = ctx.local.calibration.from_echo(monotonics::now() - instant);
+                if let Some(um) = ctx.local.filter.feed(d.um()) {
+                    let d = Distance::from_um(um);
+                    defmt::info!("Distance: {}", d);
+                    ctx.shared.stats.lock(|stats| stats.feed(d.mm()));
+                }
             }
         }
     }
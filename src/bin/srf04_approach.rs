@@ -0,0 +1,103 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Level},
+        gpiote::Gpiote,
+    };
+    use nrf_play::{
+        srf04::Srf04,
+        velocity::{Estimate, Tracker},
+    };
+    const FREQ: u32 = 64_000_000;
+    // Warn when an object would hit the sensor within a second
+    const WARN_MS: u32 = 1_000;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        sensor: Srf04<FREQ>,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        tracker: Tracker<FREQ>,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let sensor = Srf04::new(
+            p0.p0_03.into_push_pull_output(Level::Low).degrade(),
+            p0.p0_04.into_pulldown_input().degrade(),
+        );
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote
+            .channel0()
+            .input_pin(sensor.echo_pin())
+            .toggle() // Trigger on both rising and falling edges
+            .enable_interrupt();
+
+        // Tolerates a few missed pings before starting over
+        let tracker = Tracker::new(128, 32, 500.millis());
+
+        send_wave::spawn().ok();
+
+        (
+            Shared { sensor },
+            Local { gpiote, tracker },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(shared = [sensor])]
+    fn send_wave(mut ctx: send_wave::Context) {
+        ctx.shared.sensor.lock(|s| s.trigger());
+        send_wave::spawn_after(100.millis()).ok();
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [sensor], local = [gpiote, tracker])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        let now = monotonics::now();
+        if let Some(Ok(d)) = ctx.shared.sensor.lock(|s| s.on_edge(now)) {
+            if let Some(estimate) = ctx.local.tracker.update(d, now) {
+                report::spawn(estimate).ok();
+            }
+        }
+    }
+
+    #[task]
+    fn report(_: report::Context, estimate: Estimate) {
+        match estimate.time_to_contact {
+            Some(ms) if ms < WARN_MS => defmt::warn!(
+                "Collision in {} ms! {} closing at {} mm/s",
+                ms,
+                estimate.distance,
+                -estimate.velocity
+            ),
+            _ => defmt::info!("{} at {} mm/s", estimate.distance, estimate.velocity),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{fugit::MillisDurationU32, DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0, p1, Level},
+        gpiote::Gpiote,
+    };
+    use nrf_play::{
+        array::{Schedule, Srf04Array, SLOT_MS},
+        srf04::Srf04,
+    };
+    const FREQ: u32 = 64_000_000;
+    const SENSORS: usize = 4;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        array: Srf04Array<SENSORS, FREQ>,
+    }
+
+    #[local]
+    struct Local {}
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = p0::Parts::new(ctx.device.P0);
+        let p1 = p1::Parts::new(ctx.device.P1);
+        let sensors = [
+            Srf04::new(
+                p0.p0_03.into_push_pull_output(Level::Low).degrade(),
+                p0.p0_04.into_pulldown_input().degrade(),
+            ),
+            Srf04::new(
+                p0.p0_28.into_push_pull_output(Level::Low).degrade(),
+                p0.p0_29.into_pulldown_input().degrade(),
+            ),
+            Srf04::new(
+                p0.p0_30.into_push_pull_output(Level::Low).degrade(),
+                p0.p0_31.into_pulldown_input().degrade(),
+            ),
+            Srf04::new(
+                p1.p1_01.into_push_pull_output(Level::Low).degrade(),
+                p1.p1_02.into_pulldown_input().degrade(),
+            ),
+        ];
+
+        // Sensors 0/2 and 1/3 face opposite directions and can ping at the same time
+        let array = Srf04Array::new(
+            sensors,
+            Gpiote::new(ctx.device.GPIOTE),
+            Schedule::Groups(&[0b0101, 0b1010]),
+        );
+
+        scan::spawn().ok();
+        report::spawn_after(1.secs()).ok();
+
+        (Shared { array }, Local {}, init::Monotonics(mono))
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(shared = [array])]
+    fn scan(mut ctx: scan::Context) {
+        ctx.shared.array.lock(|a| a.fire_next(monotonics::now()));
+        scan::spawn_after(SLOT_MS.millis()).ok();
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [array])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.shared.array.lock(|a| a.on_gpiote(monotonics::now()));
+    }
+
+    #[task(shared = [array])]
+    fn report(mut ctx: report::Context) {
+        let readings = ctx.shared.array.lock(|a| *a.readings());
+        let now = monotonics::now();
+        for (i, reading) in readings.iter().enumerate() {
+            if let Some(r) = reading {
+                let age: MillisDurationU32 = (now - r.at).convert();
+                match r.distance {
+                    Ok(d) => defmt::info!("Sensor {}: {} ({} ms ago)", i, d, age.ticks()),
+                    Err(e) => defmt::info!("Sensor {}: {} ({} ms ago)", i, e, age.ticks()),
+                }
+            }
+        }
+        report::spawn_after(1.secs()).ok();
+    }
+}
@@ -0,0 +1,172 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// Place a target at `NEAR`, press button 1, move it to `FAR` and press button 1 again. The
+// calibration is written to flash and picked up by `Srf04` on every boot from then on.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Input, Level, Pin, PullUp},
+        gpiote::Gpiote,
+        pac::NVMC,
+        prelude::*,
+    };
+    use nrf_play::{
+        calibration::Calibration,
+        distance::Distance,
+        filter::{Filter, Median},
+        srf04::Srf04,
+    };
+    const FREQ: u32 = 64_000_000;
+    // Known target distances
+    const NEAR: Distance = Distance::from_cm(20);
+    const FAR: Distance = Distance::from_cm(100);
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        sensor: Srf04<FREQ>,
+        // Median of the last few raw readings
+        raw: Option<Distance>,
+        calibration: Calibration,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        btn: Pin<Input<PullUp>>,
+        nvmc: NVMC,
+        median: Median<5>,
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let mut sensor = Srf04::new(
+            p0.p0_03.into_push_pull_output(Level::Low).degrade(),
+            p0.p0_04.into_pulldown_input().degrade(),
+        );
+        let btn = p0.p0_11.into_pullup_input().degrade();
+
+        // Calibrate on raw readings, the stored calibration is only used for the preview
+        let calibration = sensor.calibration();
+        sensor.set_calibration(Calibration::IDENTITY);
+        defmt::info!("Current calibration: {}", calibration);
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote
+            .channel0()
+            .input_pin(sensor.echo_pin())
+            .toggle() // Trigger on both rising and falling edges
+            .enable_interrupt();
+        gpiote
+            .channel1()
+            .input_pin(&btn)
+            .hi_to_lo()
+            .enable_interrupt();
+
+        send_wave::spawn().ok();
+        report::spawn_after(1.secs()).ok();
+        defmt::info!("Place the target at {} and press button 1", NEAR);
+
+        (
+            Shared {
+                sensor,
+                raw: None,
+                calibration,
+            },
+            Local {
+                gpiote,
+                btn,
+                nvmc: ctx.device.NVMC,
+                median: Median::new(),
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(shared = [sensor])]
+    fn send_wave(mut ctx: send_wave::Context) {
+        ctx.shared.sensor.lock(|s| s.trigger());
+        send_wave::spawn_after(100.millis()).ok();
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [sensor, raw], local = [gpiote, median])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        let now = monotonics::now();
+        let gpiote = ctx.local.gpiote;
+        if gpiote.channel0().is_event_triggered() {
+            gpiote.channel0().reset_events();
+            if let Some(Ok(d)) = ctx.shared.sensor.lock(|s| s.on_edge(now)) {
+                if let Some(um) = ctx.local.median.feed(d.um()) {
+                    let d = Distance::from_um(um);
+                    ctx.shared.raw.lock(|raw| raw.replace(d));
+                }
+            }
+        }
+        if gpiote.channel1().is_event_triggered() {
+            gpiote.channel1().reset_events();
+            debounce::spawn_after(30.millis()).ok();
+        }
+    }
+
+    #[task(shared = [raw, calibration])]
+    fn report(mut ctx: report::Context) {
+        let raw = ctx.shared.raw.lock(|raw| *raw);
+        let calibration = ctx.shared.calibration.lock(|c| *c);
+        if let Some(raw) = raw {
+            defmt::info!("Raw: {}, calibrated: {}", raw, calibration.apply(raw));
+        }
+        report::spawn_after(1.secs()).ok();
+    }
+
+    #[task(shared = [raw, calibration], local = [btn, nvmc, near: Option<Distance> = None])]
+    fn debounce(mut ctx: debounce::Context) {
+        if ctx.local.btn.is_high().unwrap() {
+            return;
+        }
+        let raw = match ctx.shared.raw.lock(|raw| *raw) {
+            Some(raw) => raw,
+            None => {
+                defmt::info!("No reading yet, check the target");
+                return;
+            }
+        };
+        match ctx.local.near.take() {
+            None => {
+                defmt::info!("{} reads as {}", NEAR, raw);
+                ctx.local.near.replace(raw);
+                defmt::info!("Place the target at {} and press button 1", FAR);
+            }
+            Some(near) => {
+                defmt::info!("{} reads as {}", FAR, raw);
+                match Calibration::from_points([near, raw], [NEAR, FAR]) {
+                    Some(calibration) => {
+                        calibration.store(ctx.local.nvmc);
+                        ctx.shared.calibration.lock(|c| *c = calibration);
+                        defmt::info!("Stored calibration: {}", calibration);
+                    }
+                    None => defmt::info!("Readings don't make sense, try again"),
+                }
+                defmt::info!("Place the target at {} and press button 1", NEAR);
+            }
+        }
+    }
+}
@@ -5,7 +5,7 @@ use nrf_play as _; // global logger + panicking-behavior + memory layout
 
 #[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
 mod app {
-    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use dwt_systick_monotonic::{fugit::MicrosDurationU32, DwtSystick, ExtU32};
     use nrf52840_hal::{
         clocks::Clocks,
         gpio::{p0::Parts, Level, Output, Pin, PushPull},
@@ -13,21 +13,23 @@ mod app {
         pac::TIMER0,
         ppi,
         prelude::*,
-        timer::Timer,
     };
+    use nrf_play::{calibration::Calibration, echo::EchoCapture};
     const FREQ: u32 = 64_000_000;
 
     #[monotonic(binds = SysTick, default = true)]
     type MyMono = DwtSystick<FREQ>;
 
     #[shared]
-    struct Shared {}
+    struct Shared {
+        capture: EchoCapture<TIMER0>,
+    }
 
     #[local]
     struct Local {
         trig_pin: Pin<Output<PushPull>>,
         gpiote: Gpiote,
-        timer: Timer<TIMER0>,
+        calibration: Calibration,
     }
 
     #[init]
@@ -50,25 +52,24 @@ mod app {
             .hi_to_lo()
             .enable_interrupt();
 
-        let timer = Timer::new(ctx.device.TIMER0);
+        let capture = EchoCapture::new(ctx.device.TIMER0);
 
         let mut ppi = ppi::Parts::new(ctx.device.PPI);
         ppi.ppi0.set_event_endpoint(gpiote.channel0().event());
-        ppi.ppi0.set_task_endpoint(timer.task_start());
-        ppi.ppi0.set_fork_task_endpoint(timer.task_clear());
+        ppi.ppi0.set_task_endpoint(capture.task_capture_rise());
         ppi.ppi0.enable();
         ppi.ppi1.set_event_endpoint(gpiote.channel1().event());
-        ppi.ppi1.set_task_endpoint(timer.task_stop());
+        ppi.ppi1.set_task_endpoint(capture.task_capture_fall());
         ppi.ppi1.enable();
 
         send_wave::spawn().ok();
 
         (
-            Shared {},
+            Shared { capture },
             Local {
                 trig_pin,
                 gpiote,
-                timer,
+                calibration: Calibration::load().unwrap_or_default(),
             },
             init::Monotonics(mono),
         )
@@ -79,17 +80,34 @@ mod app {
         loop {}
     }
 
-    #[task(local = [trig_pin])]
-    fn send_wave(ctx: send_wave::Context) {
+    #[task(shared = [capture], local = [trig_pin, calibration])]
+    fn send_wave(mut ctx: send_wave::Context) {
+        let calibration = ctx.local.calibration;
+        ctx.shared.capture.lock(|capture| {
+            // Report the echoes of the previous ping before starting a new one
+            for (i, echo) in capture.echoes().iter().enumerate() {
+                let delay: MicrosDurationU32 = echo.delay.convert();
+                defmt::info!(
+                    "Echo {}: {} after {} us",
+                    i,
+                    calibration.from_echo(echo.width),
+                    delay.ticks()
+                );
+            }
+            if capture.dropped() > 0 {
+                defmt::info!("{} echoes dropped", capture.dropped());
+            }
+            capture.ping();
+        });
         ctx.local.trig_pin.set_high().ok();
         cortex_m::asm::delay(640); // 10us
         ctx.local.trig_pin.set_low().ok();
         send_wave::spawn_after(100.millis()).ok();
     }
 
-    #[task(binds = GPIOTE, local = [gpiote, timer])]
-    fn on_gpiote(ctx: on_gpiote::Context) {
+    #[task(binds = GPIOTE, priority = 2, shared = [capture], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
         ctx.local.gpiote.reset_events();
-        defmt::info!("Distance: {} cm", ctx.local.timer.read() as f32 / 58.0);
+        ctx.shared.capture.lock(|capture| capture.on_fall());
     }
 }
@@ -5,30 +5,40 @@ use nrf_play as _; // global logger + panicking-behavior + memory layout
 
 #[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
 mod app {
-    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use dwt_systick_monotonic::{fugit::MicrosDurationU32, DwtSystick, ExtU32};
     use nrf52840_hal::{
         clocks::Clocks,
-        gpio::{p0::Parts, Input, Level, Output, Pin, PullUp, PushPull},
+        gpio::{p0::Parts, Level, Output, Pin, PullUp, PushPull},
         gpiote::Gpiote,
         pac::TIMER0,
         ppi,
         prelude::*,
         timer::Timer,
     };
+    use nrf_play::{
+        button::{Button, Event, Polling, Timings, POLL_MS},
+        calibration::Calibration,
+        port::PortWatch,
+    };
     const FREQ: u32 = 64_000_000;
 
     #[monotonic(binds = SysTick, default = true)]
     type MyMono = DwtSystick<FREQ>;
 
     #[shared]
-    struct Shared {}
+    struct Shared {
+        // The button goes through the PORT event, keeping the GPIOTE channels for the echo
+        button_pin: PortWatch<PullUp, 1>,
+        polling: Polling,
+    }
 
     #[local]
     struct Local {
         gpiote: Gpiote,
-        btn: Pin<Input<PullUp>>,
+        button: Button<FREQ>,
         trig_pin: Pin<Output<PushPull>>,
         timer: Timer<TIMER0>,
+        calibration: Calibration,
     }
 
     #[init]
@@ -51,11 +61,7 @@ mod app {
             .input_pin(&echo_pin)
             .hi_to_lo()
             .enable_interrupt();
-        gpiote
-            .channel2()
-            .input_pin(&btn)
-            .hi_to_lo()
-            .enable_interrupt();
+        let button_pin = PortWatch::new([btn], &gpiote);
 
         let timer = Timer::new(ctx.device.TIMER0);
 
@@ -69,12 +75,16 @@ mod app {
         ppi.ppi1.enable();
 
         (
-            Shared {},
+            Shared {
+                button_pin,
+                polling: Polling::new(),
+            },
             Local {
                 gpiote,
-                btn,
+                button: Button::new(Timings::default()),
                 trig_pin,
                 timer,
+                calibration: Calibration::load().unwrap_or_default(),
             },
             init::Monotonics(mono),
         )
@@ -85,27 +95,43 @@ mod app {
         loop {}
     }
 
-    #[task(binds = GPIOTE, local = [gpiote, timer])]
-    fn on_gpiote(ctx: on_gpiote::Context) {
+    #[task(binds = GPIOTE, shared = [button_pin, polling], local = [gpiote, timer, calibration])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
         let (gpiote, timer) = (ctx.local.gpiote, ctx.local.timer);
         if gpiote.channel1().is_event_triggered() {
             // Echo pulse end triggered the interrupt
-            gpiote.reset_events();
-            defmt::info!("Distance: {} cm", timer.read() as f32 / 58.0);
-        } else {
-            // Button hi_to_low triggered the interrupt
-            gpiote.reset_events();
-            debounce::spawn_after(30.millis()).ok();
+            gpiote.channel1().reset_events();
+            let t = MicrosDurationU32::from_ticks(timer.read()); // Timer runs at 1 MHz
+            defmt::info!("Distance: {}", ctx.local.calibration.from_echo(t));
+        }
+        if gpiote.port().is_event_triggered() {
+            // Button PORT event triggered the interrupt, `on_port_event` clears it
+            ctx.shared.button_pin.lock(|b| b.on_port_event());
+            if ctx.shared.polling.lock(|p| p.start()) {
+                poll_button::spawn().ok();
+            }
         }
     }
 
-    #[task(local = [btn, trig_pin])]
-    fn debounce(ctx: debounce::Context) {
-        if ctx.local.btn.is_low().unwrap() {
-            // Button is pressed - send wave
-            ctx.local.trig_pin.set_high().ok();
-            cortex_m::asm::delay(640); // 10us
-            ctx.local.trig_pin.set_low().ok();
+    #[task(shared = [button_pin, polling], local = [button, trig_pin])]
+    fn poll_button(mut ctx: poll_button::Context) {
+        let button = ctx.local.button;
+        let pressed = ctx.shared.button_pin.lock(|b| b.read() == 0);
+        button.update(pressed, monotonics::now());
+        while let Some(event) = button.event() {
+            if event == Event::Pressed {
+                // Button is pressed - send wave
+                ctx.local.trig_pin.set_high().ok();
+                cortex_m::asm::delay(640); // 10us
+                ctx.local.trig_pin.set_low().ok();
+            }
+        }
+
+        let idle = button.is_idle();
+        if (ctx.shared.button_pin, ctx.shared.polling)
+            .lock(|button_pin, polling| polling.keep_going(idle && button_pin.read() != 0))
+        {
+            poll_button::spawn_after(POLL_MS.millis()).ok();
         }
     }
 }
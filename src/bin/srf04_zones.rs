@@ -0,0 +1,122 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Level, Output, Pin, PushPull},
+        gpiote::Gpiote,
+        prelude::*,
+    };
+    use nrf_play::{
+        distance::Distance,
+        srf04::Srf04,
+        zone::{ZoneChanged, Zones},
+    };
+    const FREQ: u32 = 64_000_000;
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        sensor: Srf04<FREQ>,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        zones: Zones<3, FREQ>,
+        leds: [Pin<Output<PushPull>>; 4],
+    }
+
+    #[init]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let sensor = Srf04::new(
+            p0.p0_03.into_push_pull_output(Level::Low).degrade(),
+            p0.p0_04.into_pulldown_input().degrade(),
+        );
+        // DK LEDs are active low
+        let leds = [
+            p0.p0_13.into_push_pull_output(Level::High).degrade(),
+            p0.p0_14.into_push_pull_output(Level::High).degrade(),
+            p0.p0_15.into_push_pull_output(Level::High).degrade(),
+            p0.p0_16.into_push_pull_output(Level::High).degrade(),
+        ];
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote
+            .channel0()
+            .input_pin(sensor.echo_pin())
+            .toggle() // Trigger on both rising and falling edges
+            .enable_interrupt();
+
+        // Zones: < 20 cm, 20-50 cm, 50-100 cm and beyond
+        let zones = Zones::new(
+            [
+                Distance::from_cm(20),
+                Distance::from_cm(50),
+                Distance::from_cm(100),
+            ],
+            Distance::from_cm(3),
+            300.millis(),
+        );
+
+        send_wave::spawn().ok();
+
+        (
+            Shared { sensor },
+            Local {
+                gpiote,
+                zones,
+                leds,
+            },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(shared = [sensor])]
+    fn send_wave(mut ctx: send_wave::Context) {
+        ctx.shared.sensor.lock(|s| s.trigger());
+        send_wave::spawn_after(100.millis()).ok();
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [sensor], local = [gpiote, zones])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        let now = monotonics::now();
+        if let Some(Ok(d)) = ctx.shared.sensor.lock(|s| s.on_edge(now)) {
+            if let Some(event) = ctx.local.zones.update(d, now) {
+                zone_changed::spawn(event).ok();
+            }
+        }
+    }
+
+    #[task(local = [leds])]
+    fn zone_changed(ctx: zone_changed::Context, event: ZoneChanged) {
+        defmt::info!("Zone {} -> {}", event.from, event.to);
+        for (i, led) in ctx.local.leds.iter_mut().enumerate() {
+            if i == event.to {
+                led.set_low().ok();
+            } else {
+                led.set_high().ok();
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+#![no_main]
+#![no_std]
+
+use nrf_play as _; // global logger + panicking-behavior + memory layout
+
+// SRF04 distance as a bar on an 8 LED WS2812 strip on P0.31, more LEDs and redder the closer
+// something gets. The strip is limited to 200 mA so it can run from the DK's USB supply.
+#[rtic::app(device = nrf52840_hal::pac, dispatchers = [UARTE1])]
+mod app {
+    use dwt_systick_monotonic::{DwtSystick, ExtU32};
+    use nrf52840_hal::{
+        clocks::Clocks,
+        gpio::{p0::Parts, Level},
+        gpiote::Gpiote,
+        pac::PWM0,
+    };
+    use nrf_play::{
+        distance::Distance,
+        rgb::Rgb,
+        srf04::Srf04,
+        ws2812::{buffer_len, Ws2812},
+    };
+    const FREQ: u32 = 64_000_000;
+    const LEDS: usize = 8;
+    const BUFFER: usize = buffer_len(LEDS);
+    // Full bar at NEAR, empty from FAR on
+    const NEAR: Distance = Distance::from_cm(10);
+    const FAR: Distance = Distance::from_cm(90);
+
+    #[monotonic(binds = SysTick, default = true)]
+    type MyMono = DwtSystick<FREQ>;
+
+    #[shared]
+    struct Shared {
+        sensor: Srf04<FREQ>,
+    }
+
+    #[local]
+    struct Local {
+        gpiote: Gpiote,
+        strip: Ws2812<PWM0>,
+    }
+
+    #[init(local = [buffer: [u16; BUFFER] = [0; BUFFER]])]
+    fn init(mut ctx: init::Context) -> (Shared, Local, init::Monotonics) {
+        let _clocks = Clocks::new(ctx.device.CLOCK).enable_ext_hfosc();
+
+        ctx.core.DCB.enable_trace();
+        ctx.core.DWT.enable_cycle_counter();
+        let mono = DwtSystick::new(&mut ctx.core.DCB, ctx.core.DWT, ctx.core.SYST, FREQ);
+
+        let p0 = Parts::new(ctx.device.P0);
+        let sensor = Srf04::new(
+            p0.p0_03.into_push_pull_output(Level::Low).degrade(),
+            p0.p0_04.into_pulldown_input().degrade(),
+        );
+        let mut strip = Ws2812::new(
+            ctx.device.PWM0,
+            p0.p0_31.into_push_pull_output(Level::Low).degrade(),
+            ctx.local.buffer,
+        );
+        strip.set_brightness(128);
+        strip.set_current_limit(Some(200));
+        strip.write(&[Rgb::BLACK; LEDS]);
+
+        let gpiote = Gpiote::new(ctx.device.GPIOTE);
+        gpiote
+            .channel0()
+            .input_pin(sensor.echo_pin())
+            .toggle() // Trigger on both rising and falling edges
+            .enable_interrupt();
+
+        send_wave::spawn().ok();
+
+        (
+            Shared { sensor },
+            Local { gpiote, strip },
+            init::Monotonics(mono),
+        )
+    }
+
+    #[idle]
+    fn idle(_: idle::Context) -> ! {
+        loop {}
+    }
+
+    #[task(shared = [sensor])]
+    fn send_wave(mut ctx: send_wave::Context) {
+        ctx.shared.sensor.lock(|s| s.trigger());
+        send_wave::spawn_after(100.millis()).ok();
+    }
+
+    #[task(binds = GPIOTE, priority = 2, shared = [sensor], local = [gpiote])]
+    fn on_gpiote(mut ctx: on_gpiote::Context) {
+        ctx.local.gpiote.reset_events();
+        let now = monotonics::now();
+        if let Some(Ok(d)) = ctx.shared.sensor.lock(|s| s.on_edge(now)) {
+            show::spawn(d).ok();
+        }
+    }
+
+    #[task(local = [strip])]
+    fn show(ctx: show::Context, distance: Distance) {
+        // Closeness in 1/255, 255 at NEAR
+        let mm = distance.mm().clamp(NEAR.mm(), FAR.mm());
+        let closeness = ((FAR.mm() - mm) * 255 / (FAR.mm() - NEAR.mm())) as u8;
+        let lit = (closeness as usize * LEDS).div_ceil(255);
+        let colour = Rgb::GREEN.mix(Rgb::RED, closeness);
+
+        let mut frame = [Rgb::BLACK; LEDS];
+        for led in frame[..lit].iter_mut() {
+            *led = colour;
+        }
+        defmt::debug!("{} mm, {} LEDs", distance.mm(), lit);
+        ctx.local.strip.write(&frame);
+    }
+}
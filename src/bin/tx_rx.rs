@@ -15,7 +15,10 @@ mod app {
         gpiote::Gpiote,
         prelude::*,
     };
+    use nrf_play::{calibration::Calibration, stats::Stats};
     const FREQ: u32 = 64_000_000;
+    // Round-trip times in us, 500 us buckets up to 4 ms
+    type RoundTripStats = Stats<8>;
 
     #[monotonic(binds = SysTick, default = true)]
     type MyMono = DwtSystick<FREQ>;
@@ -23,12 +26,14 @@ mod app {
     #[shared]
     struct Shared {
         tx_instant: Option<TimerInstantU32<FREQ>>,
+        stats: RoundTripStats,
     }
 
     #[local]
     struct Local {
         gpiote: Gpiote,
         tx_pin: Pin<Output<PushPull>>,
+        calibration: Calibration,
     }
 
     #[init]
@@ -51,10 +56,18 @@ mod app {
             .enable_interrupt();
 
         tx::spawn().ok();
+        summary::spawn_after(10.secs()).ok();
 
         (
-            Shared { tx_instant: None },
-            Local { gpiote, tx_pin },
+            Shared {
+                tx_instant: None,
+                stats: RoundTripStats::new(0, 500),
+            },
+            Local {
+                gpiote,
+                tx_pin,
+                calibration: Calibration::load().unwrap_or_default(),
+            },
             init::Monotonics(mono),
         )
     }
@@ -73,12 +86,23 @@ mod app {
         tx::spawn_after(100.millis()).ok();
     }
 
-    #[task(binds = GPIOTE, shared = [tx_instant], local = [gpiote])]
+    #[task(shared = [stats])]
+    fn summary(mut ctx: summary::Context) {
+        ctx.shared.stats.lock(|stats| {
+            defmt::info!("Last 10 s: {}", stats);
+            stats.reset();
+        });
+        summary::spawn_after(10.secs()).ok();
+    }
+
+    #[task(binds = GPIOTE, shared = [tx_instant, stats], local = [gpiote, calibration])]
     fn rx(mut ctx: rx::Context) {
         ctx.local.gpiote.reset_events();
         if let Some(instant) = ctx.shared.tx_instant.lock(|t| t.take()) {
             let t: MicrosDurationU32 = (monotonics::now() - instant).convert();
-            defmt::info!("Distance: {} cm", t.ticks() as f32 / 58.0);
+            let d = ctx.local.calibration.from_echo(t);
+            defmt::info!("Distance: {}", d);
+            ctx.shared.stats.lock(|stats| stats.feed(t.ticks()));
         }
     }
 }
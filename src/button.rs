@@ -0,0 +1,240 @@
+// Debounced push button with click, double click, long press and auto-repeat
+//
+// Purely a state machine over (level, time) samples, so it runs against any time source and can
+// be tested on the host. The app samples the pin every `POLL_MS` while `is_idle` is false,
+// typically starting from a GPIOTE edge interrupt, and drains the resulting events with `event`.
+// `Polling` holds whether that sampling is running, shared between the interrupt and the task.
+//
+// A level only counts once it's been stable for `debounce`. A release without a long press is a
+// click candidate: a second press within `double_click` makes it a `DoubleClick`, otherwise it's
+// reported as `Click` once that window has passed. Holding on past `long_press` gives one
+// `LongPress` and then, if enabled, `Repeat` every `repeat_interval` after `repeat_delay`.
+use crate::ring::Ring;
+use fugit::{MillisDurationU32, TimerDurationU32, TimerInstantU32};
+
+/// Sampling period while a button is busy
+pub const POLL_MS: u32 = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub enum Event {
+    Pressed,
+    Released,
+    Click,
+    DoubleClick,
+    /// Held for this many ms, sent once `long_press` is reached
+    LongPress(u32),
+    /// Auto-repeat while held after a long press
+    Repeat,
+}
+
+#[derive(Clone, Copy)]
+pub struct Timings<const FREQ: u32> {
+    /// Zero when the level comes in debounced already, like from `debounce::Debouncer`
+    pub debounce: TimerDurationU32<FREQ>,
+    /// Longest gap between the clicks of a double click, `None` sends every click right away
+    pub double_click: Option<TimerDurationU32<FREQ>>,
+    pub long_press: TimerDurationU32<FREQ>,
+    /// Time from the long press to the first repeat, `None` disables auto-repeat
+    pub repeat_delay: Option<TimerDurationU32<FREQ>>,
+    pub repeat_interval: TimerDurationU32<FREQ>,
+}
+
+impl<const FREQ: u32> Default for Timings<FREQ> {
+    fn default() -> Self {
+        Timings {
+            debounce: TimerDurationU32::millis(20),
+            double_click: Some(TimerDurationU32::millis(300)),
+            long_press: TimerDurationU32::millis(800),
+            repeat_delay: Some(TimerDurationU32::millis(400)),
+            repeat_interval: TimerDurationU32::millis(100),
+        }
+    }
+}
+
+pub struct Button<const FREQ: u32> {
+    timings: Timings<FREQ>,
+    // Debounced level
+    pressed: bool,
+    // When the raw level started to differ from `pressed`
+    changing_since: Option<TimerInstantU32<FREQ>>,
+    pressed_at: TimerInstantU32<FREQ>,
+    released_at: TimerInstantU32<FREQ>,
+    long_pressed: bool,
+    next_repeat: Option<TimerInstantU32<FREQ>>,
+    // Release time of a click that may still become a double click
+    pending_click: Option<TimerInstantU32<FREQ>>,
+    // The current press started within the double click window
+    second_press: bool,
+    events: Ring<Event, 8>,
+}
+
+impl<const FREQ: u32> Button<FREQ> {
+    pub fn new(timings: Timings<FREQ>) -> Self {
+        Button {
+            timings,
+            pressed: false,
+            changing_since: None,
+            pressed_at: TimerInstantU32::from_ticks(0),
+            released_at: TimerInstantU32::from_ticks(0),
+            long_pressed: false,
+            next_repeat: None,
+            pending_click: None,
+            second_press: false,
+            events: Ring::new(),
+        }
+    }
+
+    /// Feeds a sample of the raw button level, `true` when pressed
+    pub fn update(&mut self, raw: bool, now: TimerInstantU32<FREQ>) {
+        if raw == self.pressed {
+            self.changing_since = None;
+        } else {
+            let since = *self.changing_since.get_or_insert(now);
+            if now - since >= self.timings.debounce {
+                self.changing_since = None;
+                if raw {
+                    self.press(since);
+                } else {
+                    self.release(since);
+                }
+            }
+        }
+
+        if self.pressed {
+            self.held(now);
+        } else if let (Some(released), Some(window)) =
+            (self.pending_click, self.timings.double_click)
+        {
+            // A press that is still settling counts from where it started
+            let last = self.changing_since.unwrap_or(now);
+            if last - released > window {
+                self.pending_click = None;
+                self.events.push(Event::Click);
+            }
+        }
+    }
+
+    // Edges are dated from when the level started to change, not from when it settled
+    fn press(&mut self, at: TimerInstantU32<FREQ>) {
+        self.pressed = true;
+        self.pressed_at = at;
+        self.long_pressed = false;
+        self.next_repeat = None;
+        self.second_press = self.pending_click.is_some();
+        self.events.push(Event::Pressed);
+    }
+
+    fn release(&mut self, at: TimerInstantU32<FREQ>) {
+        self.pressed = false;
+        self.released_at = at;
+        self.events.push(Event::Released);
+        if self.long_pressed {
+            return;
+        }
+        if self.second_press {
+            self.pending_click = None;
+            self.events.push(Event::DoubleClick);
+        } else if self.timings.double_click.is_some() {
+            self.pending_click = Some(at);
+        } else {
+            self.events.push(Event::Click);
+        }
+    }
+
+    fn held(&mut self, now: TimerInstantU32<FREQ>) {
+        let held = now - self.pressed_at;
+        if !self.long_pressed && held >= self.timings.long_press {
+            self.long_pressed = true;
+            // The first click of an unfinished double click still counts
+            if self.pending_click.take().is_some() {
+                self.events.push(Event::Click);
+            }
+            let ms: MillisDurationU32 = held.convert();
+            self.events.push(Event::LongPress(ms.ticks()));
+            self.next_repeat = self.timings.repeat_delay.map(|delay| now + delay);
+        }
+        if let Some(at) = self.next_repeat {
+            if now >= at {
+                self.events.push(Event::Repeat);
+                self.next_repeat = Some(at + self.timings.repeat_interval);
+            }
+        }
+    }
+
+    /// Next event, oldest first
+    pub fn event(&mut self) -> Option<Event> {
+        self.events.pop()
+    }
+
+    /// When `update` has to be called next without a new level, for apps that don't poll
+    pub fn deadline(&self) -> Option<TimerInstantU32<FREQ>> {
+        if let Some(since) = self.changing_since {
+            Some(since + self.timings.debounce)
+        } else if self.pressed && !self.long_pressed {
+            Some(self.pressed_at + self.timings.long_press)
+        } else if self.pressed {
+            self.next_repeat
+        } else {
+            // The click goes out once the window has been exceeded
+            let window = self.timings.double_click?;
+            self.pending_click
+                .map(|released| released + window + TimerDurationU32::from_ticks(1))
+        }
+    }
+
+    /// Start of the current or last press, for timing it from its `Pressed` event on
+    ///
+    /// Edges are dated from when the level started to change, so unlike the time the event is
+    /// drained this doesn't depend on the debounce or the sampling.
+    pub fn pressed_at(&self) -> TimerInstantU32<FREQ> {
+        self.pressed_at
+    }
+
+    /// Start of the last release, see `pressed_at`
+    pub fn released_at(&self) -> TimerInstantU32<FREQ> {
+        self.released_at
+    }
+
+    /// Debounced level
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Nothing is going on, sampling can stop until the next edge
+    pub fn is_idle(&self) -> bool {
+        !self.pressed
+            && self.changing_since.is_none()
+            && self.pending_click.is_none()
+            && self.events.is_empty()
+    }
+}
+
+/// Whether the sampling task is running or scheduled
+pub struct Polling(bool);
+
+impl Polling {
+    pub const fn new() -> Self {
+        Polling(false)
+    }
+
+    /// Call on an edge, `true` if the sampling task has to be spawned
+    pub fn start(&mut self) -> bool {
+        !core::mem::replace(&mut self.0, true)
+    }
+
+    /// Call at the end of a sample with whether the buttons are idle and all pins released, `true`
+    /// if the task has to sample again
+    ///
+    /// The pins have to be read under the same lock, a change the interrupt skipped since the
+    /// sample then keeps the polling going.
+    pub fn keep_going(&mut self, idle: bool) -> bool {
+        self.0 = !idle;
+        self.0
+    }
+}
+
+impl Default for Polling {
+    fn default() -> Self {
+        Self::new()
+    }
+}
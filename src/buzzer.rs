@@ -0,0 +1,151 @@
+// Piezo buzzer on a PWM peripheral
+//
+// Each tone is a PWM sequence of a single duty cycle, held for the tone's length through the
+// sequence refresh count, with the period set for the tone's frequency. The sequence end stops the
+// PWM through a short and the app calls `on_stopped` from the PWM interrupt to start the next
+// tone, so a melody costs an interrupt per note and no task ever waits for one.
+//
+// The volume is the duty cycle, from silent at 0 to a square wave at 255. What plays is chosen by
+// priority, see `melody::Player`.
+use crate::melody::{Player, Sound, Tone};
+use nrf52840_hal::{
+    gpio::{Output, Pin, PushPull},
+    pwm::Instance,
+};
+
+// 1 MHz PWM clock, periods are in us
+const CLOCK_HZ: u32 = 1_000_000;
+// Longest period the counter takes, ~31 Hz
+const MAX_TOP: u32 = 0x7fff;
+// Rests are silent 1 kHz periods
+const REST_TOP: u32 = 1000;
+// The period starts high and falls at the compare value
+const FALLING: u16 = 0x8000;
+
+pub struct Buzzer<T: Instance> {
+    pwm: T,
+    _pin: Pin<Output<PushPull>>,
+    duty: &'static mut u16,
+    volume: u8,
+    player: Player,
+    running: bool,
+}
+
+impl<T: Instance> Buzzer<T> {
+    /// `pin` drives the buzzer and should be set low, `duty` is the value the PWM reads and must
+    /// be in RAM
+    pub fn new(pwm: T, pin: Pin<Output<PushPull>>, duty: &'static mut u16) -> Self {
+        pwm.psel.out[0].write(|w| unsafe { w.bits(pin.psel_bits()) });
+        pwm.mode.write(|w| w.updown().up());
+        pwm.prescaler.write(|w| w.prescaler().div_16());
+        pwm.decoder
+            .write(|w| w.load().common().mode().refresh_count());
+        pwm.loop_.write(|w| w.cnt().disabled());
+        pwm.seq0.enddelay.write(|w| unsafe { w.bits(0) });
+        pwm.shorts.write(|w| w.seqend0_stop().enabled());
+        pwm.intenset.write(|w| w.stopped().set());
+        Buzzer {
+            pwm,
+            _pin: pin,
+            duty,
+            volume: 128,
+            player: Player::new(),
+            running: false,
+        }
+    }
+
+    /// Sets the volume from the next tone on
+    pub fn set_volume(&mut self, volume: u8) {
+        self.volume = volume;
+    }
+
+    /// Plays `sound` once at `priority`, `false` if something of a higher priority is playing
+    pub fn play(&mut self, priority: u8, sound: Sound) -> bool {
+        self.start(priority, sound, false)
+    }
+
+    /// Plays `sound` at `priority` until stopped, `false` if something of a higher priority is
+    /// playing
+    pub fn repeat(&mut self, priority: u8, sound: Sound) -> bool {
+        self.start(priority, sound, true)
+    }
+
+    /// Stops what's playing if it was started at `priority`
+    pub fn stop(&mut self, priority: u8) {
+        if self.player.stop(priority) {
+            self.halt();
+            self.pwm.enable.write(|w| w.enable().disabled());
+        }
+    }
+
+    /// Priority of what's playing
+    pub fn playing(&self) -> Option<u8> {
+        self.player.priority()
+    }
+
+    /// Starts the next tone, call from the PWM interrupt
+    pub fn on_stopped(&mut self) {
+        // `halt` may have taken the event already
+        if self.pwm.events_stopped.read().bits() == 0 {
+            return;
+        }
+        self.pwm.events_stopped.write(|w| w);
+        self.running = false;
+        self.next();
+    }
+
+    fn start(&mut self, priority: u8, sound: Sound, repeat: bool) -> bool {
+        if !self.player.play(priority, sound, repeat) {
+            return false;
+        }
+        self.halt();
+        self.next();
+        true
+    }
+
+    fn next(&mut self) {
+        match self.player.next_tone() {
+            Some(tone) => self.tone(tone),
+            None => self.pwm.enable.write(|w| w.enable().disabled()),
+        }
+    }
+
+    fn tone(&mut self, tone: Tone) {
+        let (top, duty) = if tone.hz == 0 {
+            (REST_TOP, 0)
+        } else {
+            let top = (CLOCK_HZ / tone.hz as u32).clamp(2, MAX_TOP);
+            (top, top * self.volume as u32 / 510)
+        };
+        let periods = (tone.ms as u32 * 1000 / top).clamp(1, 0x100_0000);
+        *self.duty = FALLING | duty as u16;
+
+        self.pwm
+            .countertop
+            .write(|w| unsafe { w.countertop().bits(top as u16) });
+        self.pwm
+            .seq0
+            .ptr
+            .write(|w| unsafe { w.bits(self.duty as *const u16 as u32) });
+        self.pwm.seq0.cnt.write(|w| unsafe { w.bits(1) });
+        self.pwm
+            .seq0
+            .refresh
+            .write(|w| unsafe { w.bits(periods - 1) });
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        self.pwm.enable.write(|w| w.enable().enabled());
+        self.pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+        self.running = true;
+    }
+
+    // Stops the tone playing, without the interrupt starting another
+    fn halt(&mut self) {
+        if !self.running {
+            return;
+        }
+        self.pwm.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while self.pwm.events_stopped.read().bits() == 0 {}
+        self.pwm.events_stopped.write(|w| w);
+        self.running = false;
+    }
+}
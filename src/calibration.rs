@@ -0,0 +1,121 @@
+// Two-point ranging calibration, kept in the last page of internal flash
+//
+// The echo formula assumes the speed of sound at ~20 °C and a sensor that measures from its own
+// face, so real setups read a bit long or short and a bit too steep or flat. Measuring a target at
+// two known distances gives a straight-line correction `actual = raw * scale + offset`, which
+// `Srf04` loads from flash when it's created and applies to every reading.
+use crate::distance::Distance;
+use fugit::Duration;
+use nrf52840_hal::pac::NVMC;
+
+/// Flash page holding the calibration, the last 4 kB page of the nRF52840. memory.x leaves it out
+/// of FLASH, so the linker never places the app there.
+pub const FLASH_PAGE: u32 = 0x000f_f000;
+
+const MAGIC: u32 = 0xca1b_0001;
+const ONE: i64 = 1 << 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, defmt::Format)]
+pub struct Calibration {
+    /// Added after scaling, in um
+    pub offset: i32,
+    /// Slope in 1/65536ths
+    pub scale: u32,
+}
+
+impl Calibration {
+    /// No correction at all
+    pub const IDENTITY: Calibration = Calibration {
+        offset: 0,
+        scale: 1 << 16,
+    };
+
+    /// Fits a line through two (raw reading, actual distance) pairs
+    ///
+    /// Returns `None` if the raw readings are too close together or the slope is negative.
+    pub fn from_points(raw: [Distance; 2], actual: [Distance; 2]) -> Option<Self> {
+        let (r0, r1) = (raw[0].um() as i64, raw[1].um() as i64);
+        let (a0, a1) = (actual[0].um() as i64, actual[1].um() as i64);
+        if r0 == r1 {
+            return None;
+        }
+        let scale = (a1 - a0) * ONE / (r1 - r0);
+        if scale <= 0 || scale > u32::MAX as i64 {
+            return None;
+        }
+        let offset = a0 - r0 * scale / ONE;
+        Some(Calibration {
+            offset: offset.clamp(i32::MIN as i64, i32::MAX as i64) as i32,
+            scale: scale as u32,
+        })
+    }
+
+    /// Corrects a raw reading, clamping at zero
+    pub fn apply(&self, raw: Distance) -> Distance {
+        let um = raw.um() as i64 * self.scale as i64 / ONE + self.offset as i64;
+        Distance::from_um(um.clamp(0, u32::MAX as i64) as u32)
+    }
+
+    /// Corrected distance from an echo pulse width, `Distance::from_echo` for calibrated apps
+    pub fn from_echo<const NOM: u32, const DENOM: u32>(
+        &self,
+        echo: Duration<u32, NOM, DENOM>,
+    ) -> Distance {
+        self.apply(Distance::from_echo(echo))
+    }
+
+    /// Reads the calibration stored in flash, `None` if there is none
+    pub fn load() -> Option<Self> {
+        let page = FLASH_PAGE as *const u32;
+        // NOTE(unsafe) flash is always readable, an erased page reads as all ones
+        let words: [u32; 4] = unsafe {
+            [
+                page.read_volatile(),
+                page.add(1).read_volatile(),
+                page.add(2).read_volatile(),
+                page.add(3).read_volatile(),
+            ]
+        };
+        let [magic, offset, scale, check] = words;
+        if magic != MAGIC || check != checksum(offset, scale) {
+            return None;
+        }
+        Some(Calibration {
+            offset: offset as i32,
+            scale,
+        })
+    }
+
+    /// Erases the calibration page and writes `self` to it
+    pub fn store(&self, nvmc: &NVMC) {
+        let offset = self.offset as u32;
+        let words = [MAGIC, offset, self.scale, checksum(offset, self.scale)];
+
+        nvmc.config.write(|w| w.wen().een());
+        nvmc.erasepage().write(|w| unsafe { w.bits(FLASH_PAGE) });
+        wait_ready(nvmc);
+
+        nvmc.config.write(|w| w.wen().wen());
+        let page = FLASH_PAGE as *mut u32;
+        for (i, word) in words.iter().enumerate() {
+            // NOTE(unsafe) writes are enabled and the page was just erased
+            unsafe { page.add(i).write_volatile(*word) };
+            wait_ready(nvmc);
+        }
+        nvmc.config.write(|w| w.wen().ren());
+    }
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Calibration::IDENTITY
+    }
+}
+
+fn checksum(offset: u32, scale: u32) -> u32 {
+    !(MAGIC ^ offset ^ scale.rotate_left(16))
+}
+
+fn wait_ready(nvmc: &NVMC) {
+    while nvmc.ready.read().ready().is_busy() {}
+}
@@ -0,0 +1,143 @@
+// Chords and sequences on a handful of buttons, for commands without a UI
+//
+// Fed with the events of several `button::Button`s, numbered from 0. A chord matches when exactly
+// its buttons have been held together for its hold time, a sequence when its buttons are pressed
+// in order with no gap longer than its timeout. A match runs the command's action, which is a
+// plain function so it can spawn an RTIC task.
+use crate::button::Event;
+use fugit::{TimerDurationU32, TimerInstantU32};
+
+#[derive(Clone, Copy)]
+pub enum Pattern<const FREQ: u32> {
+    /// Button `i` is bit `i`
+    Chord {
+        buttons: u8,
+        hold: TimerDurationU32<FREQ>,
+    },
+    Sequence {
+        buttons: &'static [u8],
+        gap: TimerDurationU32<FREQ>,
+    },
+}
+
+#[derive(Clone, Copy)]
+pub struct Command<const FREQ: u32> {
+    pub name: &'static str,
+    pub pattern: Pattern<FREQ>,
+    pub action: fn(),
+}
+
+impl<const FREQ: u32> Command<FREQ> {
+    /// `false` for an empty sequence, which would match every press
+    pub fn is_valid(&self) -> bool {
+        match self.pattern {
+            Pattern::Sequence { buttons, .. } => !buttons.is_empty(),
+            Pattern::Chord { .. } => true,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Progress<const FREQ: u32> {
+    // Sequence steps done so far
+    step: usize,
+    last: TimerInstantU32<FREQ>,
+}
+
+pub struct Matcher<const N: usize, const FREQ: u32> {
+    commands: [Command<FREQ>; N],
+    progress: [Progress<FREQ>; N],
+    held: u8,
+    // When `held` last changed, and whether a chord already fired since
+    held_since: TimerInstantU32<FREQ>,
+    chord_done: bool,
+}
+
+impl<const N: usize, const FREQ: u32> Matcher<N, FREQ> {
+    /// Panics on a command that isn't `is_valid`
+    pub fn new(commands: [Command<FREQ>; N]) -> Self {
+        for command in commands.iter() {
+            assert!(command.is_valid(), "empty sequence for {}", command.name);
+        }
+        Matcher {
+            commands,
+            progress: [Progress {
+                step: 0,
+                last: TimerInstantU32::from_ticks(0),
+            }; N],
+            held: 0,
+            held_since: TimerInstantU32::from_ticks(0),
+            chord_done: false,
+        }
+    }
+
+    /// Feeds an event of button `button`, returns the name of a command that ran
+    pub fn feed(
+        &mut self,
+        button: u8,
+        event: Event,
+        now: TimerInstantU32<FREQ>,
+    ) -> Option<&'static str> {
+        let bit = 1 << button;
+        match event {
+            Event::Pressed => self.held |= bit,
+            Event::Released => self.held &= !bit,
+            _ => return None,
+        }
+        self.held_since = now;
+        self.chord_done = false;
+        if event != Event::Pressed {
+            return None;
+        }
+
+        let mut matched = None;
+        for (command, progress) in self.commands.iter().zip(self.progress.iter_mut()) {
+            let (buttons, gap) = match command.pattern {
+                Pattern::Sequence { buttons, gap } => (buttons, gap),
+                Pattern::Chord { .. } => continue,
+            };
+            let done = if now - progress.last <= gap {
+                progress.step
+            } else {
+                0
+            };
+            progress.step = advance(buttons, done, button);
+            progress.last = now;
+            if progress.step == buttons.len() {
+                progress.step = 0;
+                matched.get_or_insert(*command);
+            }
+        }
+        matched.map(Self::run)
+    }
+
+    /// Checks the chords, call regularly while buttons are held
+    pub fn tick(&mut self, now: TimerInstantU32<FREQ>) -> Option<&'static str> {
+        if self.held == 0 || self.chord_done {
+            return None;
+        }
+        let command = self.commands.iter().find(|command| match command.pattern {
+            Pattern::Chord { buttons, hold } => {
+                buttons == self.held && now - self.held_since >= hold
+            }
+            Pattern::Sequence { .. } => false,
+        })?;
+        self.chord_done = true;
+        Some(Self::run(*command))
+    }
+
+    fn run(command: Command<FREQ>) -> &'static str {
+        (command.action)();
+        command.name
+    }
+}
+
+// Steps done once `button` follows `done` of them. A wrong button falls back on the longest tail of
+// the presses so far that starts the sequence and is followed by `button`, like KMP does, so
+// [0, 0, 1] still matches 0, 0, 0, 1.
+fn advance(buttons: &[u8], done: usize, button: u8) -> usize {
+    (0..=done)
+        .rev()
+        .find(|&k| buttons[done - k..done] == buttons[..k] && buttons.get(k) == Some(&button))
+        .map_or(0, |k| k + 1)
+}
@@ -0,0 +1,53 @@
+// Hardware debouncer for one input pin
+//
+// The pin's GPIOTE event restarts a timer through PPI on every edge, without interrupting the CPU.
+// Only once the pin has been quiet for the whole period does the compare event stop the timer and
+// raise the timer interrupt, where the level read from the pin is the settled one. A bounce storm
+// costs a single interrupt at its end, however long it lasts.
+//
+// Feed the level to a `button::Button` with `Timings::debounce` at zero to get the usual events.
+use crate::mono::Instance32;
+use fugit::TimerDurationU32;
+use nrf52840_hal::pac::timer0::{TASKS_CLEAR, TASKS_START};
+
+pub const FREQ: u32 = 1_000_000;
+
+pub type Duration = TimerDurationU32<FREQ>;
+
+pub struct Debouncer<T: Instance32> {
+    timer: T,
+}
+
+impl<T: Instance32> Debouncer<T> {
+    /// Reports the level once it's been stable for `quiet`
+    pub fn new(timer: T, quiet: Duration) -> Self {
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(4) }); // 1 MHz
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.mode.write(|w| w.mode().timer());
+        timer.cc[0].write(|w| unsafe { w.bits(quiet.ticks()) });
+        timer
+            .shorts
+            .write(|w| w.compare0_stop().enabled().compare0_clear().enabled());
+        timer.intenset.write(|w| w.compare0().set());
+        Debouncer { timer }
+    }
+
+    /// Task to connect to the toggle event of the pin
+    pub fn task_restart(&self) -> &TASKS_CLEAR {
+        &self.timer.tasks_clear
+    }
+
+    /// Task to connect as the fork of the same event
+    pub fn task_start(&self) -> &TASKS_START {
+        &self.timer.tasks_start
+    }
+
+    /// Acknowledges the timer interrupt, the pin has settled
+    pub fn on_settled(&mut self) {
+        self.timer.events_compare[0].write(|w| w);
+    }
+
+    pub fn free(self) -> T {
+        self.timer
+    }
+}
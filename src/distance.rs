@@ -0,0 +1,133 @@
+// Integer distance type, so ranging works without an FPU
+use core::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+use fugit::Duration;
+
+/// Round-trip echo time per centimetre of distance, in us
+pub const US_PER_CM: u32 = 58;
+
+const UM_PER_INCH: u32 = 25_400;
+
+/// A distance with micrometre resolution
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Distance(u32);
+
+impl Distance {
+    pub const ZERO: Distance = Distance(0);
+    pub const MAX: Distance = Distance(u32::MAX);
+
+    pub const fn from_um(um: u32) -> Self {
+        Distance(um)
+    }
+
+    pub const fn from_mm(mm: u32) -> Self {
+        Distance(mm * 1_000)
+    }
+
+    pub const fn from_cm(cm: u32) -> Self {
+        Distance(cm * 10_000)
+    }
+
+    pub const fn from_inches(inches: u32) -> Self {
+        Distance(inches * UM_PER_INCH)
+    }
+
+    /// Distance to an object from the width of its ultrasonic echo pulse
+    pub const fn from_echo<const NOM: u32, const DENOM: u32>(
+        echo: Duration<u32, NOM, DENOM>,
+    ) -> Self {
+        // um = us * 10_000 / US_PER_CM, computed from the raw ticks to keep sub-us resolution
+        let um = echo.ticks() as u128 * NOM as u128 * 10_000_000_000
+            / (DENOM as u128 * US_PER_CM as u128);
+        if um > u32::MAX as u128 {
+            Distance::MAX
+        } else {
+            Distance(um as u32)
+        }
+    }
+
+    pub const fn um(self) -> u32 {
+        self.0
+    }
+
+    /// Whole millimetres, rounded to nearest
+    pub const fn mm(self) -> u32 {
+        rounded_div(self.0, 1_000)
+    }
+
+    /// Whole centimetres, rounded to nearest
+    pub const fn cm(self) -> u32 {
+        rounded_div(self.0, 10_000)
+    }
+
+    /// Whole inches, rounded to nearest
+    pub const fn inches(self) -> u32 {
+        rounded_div(self.0, UM_PER_INCH)
+    }
+
+    pub const fn abs_diff(self, other: Distance) -> Distance {
+        if self.0 > other.0 {
+            Distance(self.0 - other.0)
+        } else {
+            Distance(other.0 - self.0)
+        }
+    }
+
+    pub const fn saturating_sub(self, other: Distance) -> Distance {
+        Distance(self.0.saturating_sub(other.0))
+    }
+}
+
+const fn rounded_div(n: u32, d: u32) -> u32 {
+    n / d + (n % d >= d / 2) as u32
+}
+
+impl Add for Distance {
+    type Output = Distance;
+
+    fn add(self, rhs: Distance) -> Distance {
+        Distance(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Distance {
+    fn add_assign(&mut self, rhs: Distance) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Distance {
+    type Output = Distance;
+
+    fn sub(self, rhs: Distance) -> Distance {
+        Distance(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Distance {
+    fn sub_assign(&mut self, rhs: Distance) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Mul<u32> for Distance {
+    type Output = Distance;
+
+    fn mul(self, rhs: u32) -> Distance {
+        Distance(self.0 * rhs)
+    }
+}
+
+impl Div<u32> for Distance {
+    type Output = Distance;
+
+    fn div(self, rhs: u32) -> Distance {
+        Distance(self.0 / rhs)
+    }
+}
+
+impl defmt::Format for Distance {
+    fn format(&self, f: defmt::Formatter) {
+        let mm = self.mm();
+        defmt::write!(f, "{}.{} cm", mm / 10, mm % 10)
+    }
+}
@@ -0,0 +1,121 @@
+// Multi-echo capture on a free-running 16 MHz timer
+//
+// Instead of starting/clearing/stopping the timer on echo edges, the timer runs freely and the
+// edges are routed through PPI to capture tasks: rising edges to CC[1], falling edges to CC[2].
+// The edge times are therefore latched in hardware, the falling edge interrupt only has to move
+// CC[1]/CC[2] into the echo list before the next echo starts and overwrites CC[1]. CC[0] holds
+// the time of the ping.
+use crate::mono::Instance32;
+use fugit::{TimerDurationU32, TimerInstantU32};
+use nrf52840_hal::pac::timer0::TASKS_CAPTURE;
+
+pub const FREQ: u32 = 16_000_000;
+pub const MAX_ECHOES: usize = 4;
+
+pub type Instant = TimerInstantU32<FREQ>;
+pub type Duration = TimerDurationU32<FREQ>;
+
+const CC_PING: usize = 0;
+const CC_RISE: usize = 1;
+const CC_FALL: usize = 2;
+const CC_NOW: usize = 3;
+
+#[derive(Clone, Copy)]
+pub struct Echo {
+    /// Time from the ping to the rising edge
+    pub delay: Duration,
+    /// Width of the echo pulse
+    pub width: Duration,
+}
+
+pub struct EchoCapture<T: Instance32> {
+    timer: T,
+    ping: Instant,
+    echoes: [Echo; MAX_ECHOES],
+    len: usize,
+    // Echoes beyond `MAX_ECHOES`
+    dropped: usize,
+}
+
+impl<T: Instance32> EchoCapture<T> {
+    /// Starts `timer` free-running at 16 MHz
+    pub fn new(timer: T) -> Self {
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(0) }); // 16 MHz
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.mode.write(|w| w.mode().timer());
+        timer.shorts.reset();
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+        let zero = Echo {
+            delay: Duration::from_ticks(0),
+            width: Duration::from_ticks(0),
+        };
+        EchoCapture {
+            timer,
+            ping: Instant::from_ticks(0),
+            echoes: [zero; MAX_ECHOES],
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Capture task to connect to the rising edge event of the echo pin
+    pub fn task_capture_rise(&self) -> &TASKS_CAPTURE {
+        &self.timer.tasks_capture[CC_RISE]
+    }
+
+    /// Capture task to connect to the falling edge event of the echo pin
+    pub fn task_capture_fall(&self) -> &TASKS_CAPTURE {
+        &self.timer.tasks_capture[CC_FALL]
+    }
+
+    /// Current timer value
+    pub fn now(&self) -> Instant {
+        self.timer.tasks_capture[CC_NOW].write(|w| unsafe { w.bits(1) });
+        Instant::from_ticks(self.timer.cc[CC_NOW].read().bits())
+    }
+
+    /// Marks the time of a new ping and forgets the echoes of the last one
+    ///
+    /// Call right before sending the trigger pulse.
+    pub fn ping(&mut self) {
+        self.timer.tasks_capture[CC_PING].write(|w| unsafe { w.bits(1) });
+        self.ping = Instant::from_ticks(self.timer.cc[CC_PING].read().bits());
+        self.len = 0;
+        self.dropped = 0;
+    }
+
+    /// Time of the last ping
+    pub fn ping_time(&self) -> Instant {
+        self.ping
+    }
+
+    /// Collects the echo that just ended, call from the falling edge interrupt
+    pub fn on_fall(&mut self) {
+        let rise = Instant::from_ticks(self.timer.cc[CC_RISE].read().bits());
+        let fall = Instant::from_ticks(self.timer.cc[CC_FALL].read().bits());
+        if self.len == MAX_ECHOES {
+            self.dropped += 1;
+            return;
+        }
+        self.echoes[self.len] = Echo {
+            delay: rise - self.ping,
+            width: fall - rise,
+        };
+        self.len += 1;
+    }
+
+    /// Echoes of the last ping, in order of arrival
+    pub fn echoes(&self) -> &[Echo] {
+        &self.echoes[..self.len]
+    }
+
+    /// Number of echoes that didn't fit in the list
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn free(self) -> T {
+        self.timer
+    }
+}
@@ -0,0 +1,170 @@
+// Filters for noisy range readings
+//
+// All filters work on plain `u32` samples (echo widths in us, distances in um, ..) so they can
+// run without an FPU and be tested on the host. Filters are chained with `Filter::chain`, each
+// stage only seeing the samples the previous stage let through.
+
+pub trait Filter {
+    /// Feeds a new sample, returns the filtered value or `None` if the sample was dropped
+    fn feed(&mut self, sample: u32) -> Option<u32>;
+
+    /// Forgets all previous samples
+    fn reset(&mut self);
+
+    /// Passes the output of `self` on to `next`
+    fn chain<F: Filter>(self, next: F) -> Chain<Self, F>
+    where
+        Self: Sized,
+    {
+        Chain::new(self, next)
+    }
+}
+
+/// Two filters run back to back
+pub struct Chain<A, B>(A, B);
+
+impl<A: Filter, B: Filter> Chain<A, B> {
+    pub const fn new(first: A, second: B) -> Self {
+        Chain(first, second)
+    }
+}
+
+impl<A: Filter, B: Filter> Filter for Chain<A, B> {
+    fn feed(&mut self, sample: u32) -> Option<u32> {
+        self.0.feed(sample).and_then(|s| self.1.feed(s))
+    }
+
+    fn reset(&mut self) {
+        self.0.reset();
+        self.1.reset();
+    }
+}
+
+/// Median of the last `N` samples
+///
+/// Until the window is full the median of the samples seen so far is returned.
+pub struct Median<const N: usize> {
+    buf: [u32; N],
+    len: usize,
+    pos: usize,
+}
+
+impl<const N: usize> Median<N> {
+    pub const fn new() -> Self {
+        Median {
+            buf: [0; N],
+            len: 0,
+            pos: 0,
+        }
+    }
+}
+
+impl<const N: usize> Default for Median<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Filter for Median<N> {
+    fn feed(&mut self, sample: u32) -> Option<u32> {
+        if N == 0 {
+            return Some(sample);
+        }
+        self.buf[self.pos] = sample;
+        self.pos = (self.pos + 1) % N;
+        self.len = (self.len + 1).min(N);
+
+        let mut sorted = self.buf;
+        let sorted = &mut sorted[..self.len];
+        sorted.sort_unstable();
+        let mid = self.len / 2;
+        if self.len.is_multiple_of(2) {
+            // Average the two middle samples, written so that it can't overflow
+            let (a, b) = (sorted[mid - 1], sorted[mid]);
+            Some(a + (b - a) / 2)
+        } else {
+            Some(sorted[mid])
+        }
+    }
+
+    fn reset(&mut self) {
+        self.len = 0;
+        self.pos = 0;
+    }
+}
+
+/// Exponential moving average with a smoothing factor of `1 / 2^SHIFT`
+///
+/// The state is kept with `SHIFT` extra fractional bits so small steps aren't rounded away.
+pub struct Ema<const SHIFT: u32> {
+    acc: Option<u64>,
+}
+
+impl<const SHIFT: u32> Ema<SHIFT> {
+    pub const fn new() -> Self {
+        Ema { acc: None }
+    }
+}
+
+impl<const SHIFT: u32> Default for Ema<SHIFT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SHIFT: u32> Filter for Ema<SHIFT> {
+    fn feed(&mut self, sample: u32) -> Option<u32> {
+        let acc = match self.acc {
+            // The first sample seeds the average
+            None => (sample as u64) << SHIFT,
+            Some(acc) => acc - (acc >> SHIFT) + sample as u64,
+        };
+        self.acc = Some(acc);
+        Some((acc >> SHIFT) as u32)
+    }
+
+    fn reset(&mut self) {
+        self.acc = None;
+    }
+}
+
+/// Drops samples that differ more than `max_delta` from the last accepted one
+///
+/// After `max_rejects` consecutive drops the next sample is accepted anyway, so the filter
+/// follows real jumps (e.g. a new object in front of the sensor) instead of locking up.
+pub struct OutlierReject {
+    max_delta: u32,
+    max_rejects: u8,
+    rejects: u8,
+    last: Option<u32>,
+}
+
+impl OutlierReject {
+    pub const fn new(max_delta: u32, max_rejects: u8) -> Self {
+        OutlierReject {
+            max_delta,
+            max_rejects,
+            rejects: 0,
+            last: None,
+        }
+    }
+}
+
+impl Filter for OutlierReject {
+    fn feed(&mut self, sample: u32) -> Option<u32> {
+        if let Some(last) = self.last {
+            if sample.abs_diff(last) > self.max_delta && self.rejects < self.max_rejects {
+                self.rejects += 1;
+                return None;
+            }
+        }
+        self.rejects = 0;
+        self.last = Some(sample);
+        Some(sample)
+    }
+
+    fn reset(&mut self) {
+        self.rejects = 0;
+        self.last = None;
+    }
+}
@@ -0,0 +1,200 @@
+// Frequency counter, two timers and PPI, no CPU time per edge
+//
+// The input edges are routed through GPIOTE and PPI to the COUNT task of a timer in counter mode.
+// A second timer at 16 MHz runs the measurement and is wired up both ways:
+//
+// - Gated: gate compares CC[0]/CC[1] capture the edge count into counter CC[2]/CC[3], so the
+//   window is exact to the 16 MHz tick. Good for high frequencies, where a window holds many
+//   edges.
+// - Reciprocal: counter compares CC[0]/CC[1] fire on the first edge and `periods` edges later
+//   and capture the gate time into gate CC[2]/CC[3]. Resolution is one 16 MHz tick over the whole
+//   run regardless of the input frequency, which is what low frequencies need.
+//
+// Both run at the same time, `read` picks the better one and sizes the window and the number of
+// periods for the next measurement from the result.
+use crate::mono::Instance32;
+use fugit::TimerDurationU32;
+use nrf52840_hal::pac::timer0::{EVENTS_COMPARE, TASKS_CAPTURE, TASKS_COUNT};
+
+pub const FREQ: u32 = 16_000_000;
+
+pub type Duration = TimerDurationU32<FREQ>;
+
+/// Gate windows to pick from, shortest first
+pub const WINDOWS_MS: [u32; 3] = [10, 100, 1_000];
+/// Gated counts needed to prefer the gated result, 1e-4 resolution
+pub const MIN_GATED_COUNT: u32 = 10_000;
+
+// Gate timer
+const CC_WINDOW_START: usize = 0;
+const CC_WINDOW_END: usize = 1;
+const CC_FIRST_EDGE_TIME: usize = 2;
+const CC_LAST_EDGE_TIME: usize = 3;
+// Counter timer
+const CC_FIRST_EDGE: usize = 0;
+const CC_LAST_EDGE: usize = 1;
+const CC_WINDOW_START_COUNT: usize = 2;
+const CC_WINDOW_END_COUNT: usize = 3;
+
+// Leave the timers a moment to get going before the window opens
+const START_AT: u32 = 16;
+
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum Method {
+    Gated,
+    Reciprocal,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub millihertz: u64,
+    pub method: Method,
+}
+
+impl defmt::Format for Measurement {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(
+            f,
+            "{=u64}.{=u64:03} Hz ({})",
+            self.millihertz / 1_000,
+            self.millihertz % 1_000,
+            self.method
+        )
+    }
+}
+
+pub struct FreqCounter<C: Instance32, G: Instance32> {
+    counter: C,
+    gate: G,
+    window: u32,
+    periods: u32,
+}
+
+impl<C: Instance32, G: Instance32> FreqCounter<C, G> {
+    /// `counter` counts the input edges, `gate` times the measurement at 16 MHz
+    pub fn new(counter: C, gate: G) -> Self {
+        counter.bitmode.write(|w| w.bitmode()._32bit());
+        counter.mode.write(|w| w.mode().counter());
+        gate.prescaler.write(|w| unsafe { w.prescaler().bits(0) }); // 16 MHz
+        gate.bitmode.write(|w| w.bitmode()._32bit());
+        gate.mode.write(|w| w.mode().timer());
+        FreqCounter {
+            counter,
+            gate,
+            window: window_ticks(WINDOWS_MS[WINDOWS_MS.len() - 1]),
+            periods: 1,
+        }
+    }
+
+    /// Count task to connect to the input edge event
+    pub fn task_count(&self) -> &TASKS_COUNT {
+        &self.counter.tasks_count
+    }
+
+    /// Gate events to connect to `task_capture_start`/`end`
+    pub fn event_window_start(&self) -> &EVENTS_COMPARE {
+        &self.gate.events_compare[CC_WINDOW_START]
+    }
+
+    pub fn event_window_end(&self) -> &EVENTS_COMPARE {
+        &self.gate.events_compare[CC_WINDOW_END]
+    }
+
+    pub fn task_capture_start(&self) -> &TASKS_CAPTURE {
+        &self.counter.tasks_capture[CC_WINDOW_START_COUNT]
+    }
+
+    pub fn task_capture_end(&self) -> &TASKS_CAPTURE {
+        &self.counter.tasks_capture[CC_WINDOW_END_COUNT]
+    }
+
+    /// Counter events to connect to `task_capture_first`/`last`
+    pub fn event_first_edge(&self) -> &EVENTS_COMPARE {
+        &self.counter.events_compare[CC_FIRST_EDGE]
+    }
+
+    pub fn event_last_edge(&self) -> &EVENTS_COMPARE {
+        &self.counter.events_compare[CC_LAST_EDGE]
+    }
+
+    pub fn task_capture_first(&self) -> &TASKS_CAPTURE {
+        &self.gate.tasks_capture[CC_FIRST_EDGE_TIME]
+    }
+
+    pub fn task_capture_last(&self) -> &TASKS_CAPTURE {
+        &self.gate.tasks_capture[CC_LAST_EDGE_TIME]
+    }
+
+    /// Starts a measurement, call `read` once `duration` has passed
+    pub fn start(&mut self) {
+        for timer in [&*self.counter, &*self.gate] {
+            timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+            timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+            for cc in timer.cc.iter() {
+                cc.write(|w| unsafe { w.bits(0) });
+            }
+        }
+        self.counter.cc[CC_FIRST_EDGE].write(|w| unsafe { w.bits(1) });
+        self.counter.cc[CC_LAST_EDGE].write(|w| unsafe { w.bits(1 + self.periods) });
+        self.gate.cc[CC_WINDOW_START].write(|w| unsafe { w.bits(START_AT) });
+        self.gate.cc[CC_WINDOW_END].write(|w| unsafe { w.bits(START_AT + self.window) });
+        self.counter.tasks_start.write(|w| unsafe { w.bits(1) });
+        self.gate.tasks_start.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// How long a measurement takes, long enough for the reciprocal run to finish if the input
+    /// frequency hasn't dropped
+    pub fn duration(&self) -> Duration {
+        Duration::from_ticks(START_AT + 2 * self.window)
+    }
+
+    /// Ends the measurement and picks the better result, `None` if no edges came in
+    pub fn read(&mut self) -> Option<Measurement> {
+        self.counter.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.gate.tasks_stop.write(|w| unsafe { w.bits(1) });
+
+        let gated = self.counter.cc[CC_WINDOW_END_COUNT]
+            .read()
+            .bits()
+            .wrapping_sub(self.counter.cc[CC_WINDOW_START_COUNT].read().bits());
+        let first = self.gate.cc[CC_FIRST_EDGE_TIME].read().bits();
+        let last = self.gate.cc[CC_LAST_EDGE_TIME].read().bits();
+
+        let measurement = if gated < MIN_GATED_COUNT && last > first && first > 0 {
+            Some(Measurement {
+                millihertz: self.periods as u64 * FREQ as u64 * 1_000 / (last - first) as u64,
+                method: Method::Reciprocal,
+            })
+        } else if gated > 0 {
+            Some(Measurement {
+                millihertz: gated as u64 * FREQ as u64 * 1_000 / self.window as u64,
+                method: Method::Gated,
+            })
+        } else {
+            None
+        };
+        self.range(measurement.map_or(0, |m| m.millihertz));
+        measurement
+    }
+
+    // Picks the shortest window that still gives a precise gated count, and makes the reciprocal
+    // run about a window long
+    fn range(&mut self, millihertz: u64) {
+        let ms = WINDOWS_MS
+            .iter()
+            .copied()
+            .find(|&ms| millihertz * ms as u64 / 1_000_000 >= MIN_GATED_COUNT as u64)
+            .unwrap_or(WINDOWS_MS[WINDOWS_MS.len() - 1]);
+        self.window = window_ticks(ms);
+        let periods = millihertz * ms as u64 / 1_000_000;
+        self.periods = periods.clamp(1, u32::MAX as u64 - 1) as u32;
+    }
+
+    pub fn free(self) -> (C, G) {
+        (self.counter, self.gate)
+    }
+}
+
+fn window_ticks(ms: u32) -> u32 {
+    ms * (FREQ / 1_000)
+}
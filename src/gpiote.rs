@@ -0,0 +1,21 @@
+// GPIOTE channels by index
+//
+// The HAL only hands out channels through one method per channel. Drivers that bind channels at
+// run time, like `array` and `logic`, pick them by number here.
+use nrf52840_hal::gpiote::{Gpiote, GpioteChannel};
+
+pub const NUM_CHANNELS: usize = 8;
+
+/// Channel `ch`, anything past the last one is the last one
+pub fn channel(gpiote: &Gpiote, ch: usize) -> GpioteChannel<'_> {
+    match ch {
+        0 => gpiote.channel0(),
+        1 => gpiote.channel1(),
+        2 => gpiote.channel2(),
+        3 => gpiote.channel3(),
+        4 => gpiote.channel4(),
+        5 => gpiote.channel5(),
+        6 => gpiote.channel6(),
+        _ => gpiote.channel7(),
+    }
+}
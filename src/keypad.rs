@@ -0,0 +1,92 @@
+// Key matrix scanner
+//
+// Rows are open-drain outputs and columns pulled-up inputs. A scan pulls one row low at a time and
+// reads which columns follow, a closed key connecting the two. The raw scans go to
+// `matrix::KeyMatrix` for debouncing and ghosting detection.
+//
+// With no key down the scanner stops: all rows are pulled low and the columns are watched through
+// the PORT event, so the CPU can sleep until the next key press.
+use crate::{
+    matrix::{KeyEvent, KeyMatrix},
+    port::PortWatch,
+};
+use fugit::{TimerDurationU32, TimerInstantU32};
+use nrf52840_hal::{
+    gpio::{Input, OpenDrain, Output, Pin, PullUp},
+    gpiote::Gpiote,
+    prelude::*,
+};
+
+// Time for the columns to follow a row, ~1 us at 64 MHz
+const SETTLE_CYCLES: u32 = 64;
+
+pub struct Keypad<const R: usize, const C: usize, const FREQ: u32> {
+    rows: [Pin<Output<OpenDrain>>; R],
+    cols: PortWatch<PullUp, C>,
+    matrix: KeyMatrix<R, C, FREQ>,
+}
+
+impl<const R: usize, const C: usize, const FREQ: u32> Keypad<R, C, FREQ> {
+    const COLS: u32 = (1 << C) - 1;
+
+    /// Starts out waiting for a key press, with the PORT interrupt enabled
+    pub fn new(
+        rows: [Pin<Output<OpenDrain>>; R],
+        cols: [Pin<Input<PullUp>>; C],
+        gpiote: &Gpiote,
+        debounce: TimerDurationU32<FREQ>,
+    ) -> Self {
+        let mut keypad = Keypad {
+            rows,
+            cols: PortWatch::new(cols, gpiote),
+            matrix: KeyMatrix::new(debounce),
+        };
+        for row in keypad.rows.iter_mut() {
+            row.set_low().unwrap();
+        }
+        keypad.cols.resume();
+        keypad
+    }
+
+    /// Handles the PORT event, scanning should start
+    pub fn on_port_event(&mut self) {
+        self.cols.on_port_event();
+        self.cols.pause();
+    }
+
+    /// Scans all keys, returns `false` once no more scans are needed until the next PORT event
+    pub fn scan(&mut self, now: TimerInstantU32<FREQ>) -> bool {
+        self.cols.pause();
+        for row in self.rows.iter_mut() {
+            row.set_high().unwrap();
+        }
+        let mut raw = 0;
+        for (r, row) in self.rows.iter_mut().enumerate() {
+            row.set_low().unwrap();
+            cortex_m::asm::delay(SETTLE_CYCLES);
+            raw |= (!self.cols.read() & Self::COLS) << (r * C);
+            row.set_high().unwrap();
+        }
+        self.matrix.update(raw, now);
+        if !self.matrix.is_idle() {
+            return true;
+        }
+
+        for row in self.rows.iter_mut() {
+            row.set_low().unwrap();
+        }
+        cortex_m::asm::delay(SETTLE_CYCLES);
+        self.cols.resume();
+        // A key closed before sensing started wouldn't raise the PORT event
+        self.cols.read() != Self::COLS
+    }
+
+    /// Next key event, oldest first
+    pub fn event(&mut self) -> Option<KeyEvent<FREQ>> {
+        self.matrix.event()
+    }
+
+    pub fn matrix(&self) -> &KeyMatrix<R, C, FREQ> {
+        &self.matrix
+    }
+}
@@ -0,0 +1,88 @@
+// Propagation delay measurement timed entirely by a 16 MHz timer
+//
+// Every run clears the timer and lets it drive the TX pulse through compare events: CC[0] raises
+// the TX pin and CC[1] lowers it again, via PPI and GPIOTE tasks. The RX rising edge is captured
+// into CC[2] through PPI, and CC[3] ends the run by stopping the timer. Both ends of the delay are
+// thus timestamped in hardware, the only software involved is reading the result afterwards.
+//
+// The result includes GPIOTE's own fixed task and event latency, measure a plain wire from TX to
+// RX first and subtract that from later runs.
+use crate::mono::Instance32;
+use fugit::TimerDurationU32;
+use nrf52840_hal::pac::timer0::{EVENTS_COMPARE, TASKS_CAPTURE};
+
+pub const FREQ: u32 = 16_000_000;
+
+pub type Duration = TimerDurationU32<FREQ>;
+
+const CC_TX: usize = 0;
+const CC_TX_END: usize = 1;
+const CC_RX: usize = 2;
+const CC_DONE: usize = 3;
+
+// Leave the timer a moment to get going before the TX edge
+const TX_AT: u32 = 16;
+
+pub struct LatencyMeter<T: Instance32> {
+    timer: T,
+}
+
+impl<T: Instance32> LatencyMeter<T> {
+    /// Sends `pulse`-wide TX pulses and gives up on the RX edge after `timeout`
+    pub fn new(timer: T, pulse: Duration, timeout: Duration) -> Self {
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(0) }); // 16 MHz
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.mode.write(|w| w.mode().timer());
+        timer.cc[CC_TX].write(|w| unsafe { w.bits(TX_AT) });
+        timer.cc[CC_TX_END].write(|w| unsafe { w.bits(TX_AT + pulse.ticks()) });
+        timer.cc[CC_DONE].write(|w| unsafe { w.bits(TX_AT + timeout.ticks()) });
+        timer.shorts.write(|w| w.compare3_stop().enabled());
+        timer.intenset.write(|w| w.compare3().set());
+        LatencyMeter { timer }
+    }
+
+    /// Event to connect to the GPIOTE set task of the TX pin
+    pub fn event_tx_start(&self) -> &EVENTS_COMPARE {
+        &self.timer.events_compare[CC_TX]
+    }
+
+    /// Event to connect to the GPIOTE clear task of the TX pin
+    pub fn event_tx_end(&self) -> &EVENTS_COMPARE {
+        &self.timer.events_compare[CC_TX_END]
+    }
+
+    /// Capture task to connect to the rising edge event of the RX pin
+    pub fn task_capture_rx(&self) -> &TASKS_CAPTURE {
+        &self.timer.tasks_capture[CC_RX]
+    }
+
+    /// Starts a run, the timer interrupt fires when it's over
+    pub fn start(&mut self) {
+        self.timer.tasks_stop.write(|w| unsafe { w.bits(1) });
+        self.timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        self.timer.cc[CC_RX].write(|w| unsafe { w.bits(0) });
+        self.timer.tasks_start.write(|w| unsafe { w.bits(1) });
+    }
+
+    /// Collects the result of a run, call from the timer interrupt
+    ///
+    /// Returns `None` if no RX edge was seen after the TX edge.
+    pub fn on_done(&mut self) -> Option<Duration> {
+        self.timer.events_compare[CC_DONE].write(|w| w);
+        let rx = self.timer.cc[CC_RX].read().bits();
+        if rx > TX_AT {
+            Some(Duration::from_ticks(rx - TX_AT))
+        } else {
+            None
+        }
+    }
+
+    pub fn free(self) -> T {
+        self.timer
+    }
+}
+
+/// Converts timer ticks to ns, rounded down to the nearest ns
+pub fn nanos(d: Duration) -> u32 {
+    d.ticks() * 125 / 2
+}
@@ -0,0 +1,122 @@
+// LED pattern player on one PWM peripheral
+//
+// The patterns of all four LEDs are rendered into a frame of duty cycles, one per LED and step,
+// which the PWM plays from RAM in a loop: each value is held for `STEP_MS` through the sequence
+// refresh count, and the end of the loop restarts it through a short. Once a frame is loaded the
+// CPU isn't involved at all. Changing a pattern renders the next frame into the other buffer and
+// switches over, which restarts every LED's pattern from the top.
+//
+// The DK LEDs are active low, so the PWM period starts low for the on time and then goes high.
+use crate::pattern::{frame_steps, Layers, Pattern, MAX_STEPS, STEP_MS};
+use nrf52840_hal::{
+    gpio::{Output, Pin, PushPull},
+    pwm::Instance,
+};
+
+// 1 MHz PWM clock, 1 ms period
+const TOP: u32 = 1000;
+
+/// Double buffered frames, must be in RAM for the PWM's DMA
+pub type Frames = [[[u16; 4]; MAX_STEPS]; 2];
+
+pub struct Leds<T: Instance> {
+    pwm: T,
+    _pins: [Pin<Output<PushPull>>; 4],
+    frames: &'static mut Frames,
+    // Frame being played
+    playing: usize,
+    layers: [Layers<4>; 4],
+    shown: [Pattern; 4],
+}
+
+impl<T: Instance> Leds<T> {
+    /// Takes the LED pins, which should be set high so the LEDs are off without the PWM
+    pub fn new(pwm: T, pins: [Pin<Output<PushPull>>; 4], frames: &'static mut Frames) -> Self {
+        for (out, pin) in pwm.psel.out.iter().zip(pins.iter()) {
+            out.write(|w| unsafe { w.bits(pin.psel_bits()) });
+        }
+        pwm.mode.write(|w| w.updown().up());
+        pwm.prescaler.write(|w| w.prescaler().div_16());
+        pwm.countertop
+            .write(|w| unsafe { w.countertop().bits(TOP as u16) });
+        pwm.decoder
+            .write(|w| w.load().individual().mode().refresh_count());
+        // Both sequences play the same frame, the loop end starts over
+        pwm.loop_.write(|w| unsafe { w.cnt().bits(1) });
+        pwm.shorts.write(|w| w.loopsdone_seqstart0().enabled());
+        for seq in [&pwm.seq0, &pwm.seq1] {
+            seq.refresh
+                .write(|w| unsafe { w.bits(STEP_MS * 1000 / TOP - 1) });
+            seq.enddelay.write(|w| unsafe { w.bits(0) });
+        }
+        Leds {
+            pwm,
+            _pins: pins,
+            frames,
+            playing: 0,
+            layers: Default::default(),
+            shown: [Pattern::Off; 4],
+        }
+    }
+
+    /// Requests `pattern` on LED `led` at `priority`, higher ones hide the lower ones
+    pub fn set(&mut self, led: usize, priority: u8, pattern: Pattern) -> bool {
+        let set = self.layers[led].set(priority, pattern);
+        self.refresh();
+        set
+    }
+
+    /// Withdraws the pattern on LED `led` at `priority`
+    pub fn clear(&mut self, led: usize, priority: u8) {
+        self.layers[led].clear(priority);
+        self.refresh();
+    }
+
+    /// Pattern shown on LED `led`
+    pub fn current(&self, led: usize) -> Pattern {
+        self.shown[led]
+    }
+
+    fn refresh(&mut self) {
+        let patterns = [0, 1, 2, 3].map(|led| self.layers[led].current());
+        if patterns == self.shown {
+            return;
+        }
+        self.shown = patterns;
+
+        if patterns.iter().all(|p| *p == Pattern::Off) {
+            self.stop();
+            return;
+        }
+
+        let next = 1 - self.playing;
+        let n = frame_steps(&patterns);
+        for (step, values) in self.frames[next][..n].iter_mut().enumerate() {
+            for (value, pattern) in values.iter_mut().zip(patterns.iter()) {
+                *value = (pattern.level(step) as u32 * TOP / 255) as u16;
+            }
+        }
+
+        let ptr = self.frames[next].as_ptr() as u32;
+        // Four values per step
+        let count = 4 * n as u32;
+        for seq in [&self.pwm.seq0, &self.pwm.seq1] {
+            seq.ptr.write(|w| unsafe { w.bits(ptr) });
+            seq.cnt.write(|w| unsafe { w.bits(count) });
+        }
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        self.pwm.enable.write(|w| w.enable().enabled());
+        self.pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+        self.playing = next;
+    }
+
+    fn stop(&mut self) {
+        if self.pwm.enable.read().enable().is_disabled() {
+            return;
+        }
+        self.pwm.tasks_stop.write(|w| unsafe { w.bits(1) });
+        while self.pwm.events_stopped.read().bits() == 0 {}
+        self.pwm.events_stopped.write(|w| w);
+        self.pwm.enable.write(|w| w.enable().disabled());
+    }
+}
@@ -4,7 +4,39 @@ use core::sync::atomic::{AtomicUsize, Ordering};
 
 use defmt_rtt as _; // global logger
 use nrf52840_hal as _; // memory layout
+pub mod array;
+pub mod button;
+pub mod buzzer;
+pub mod calibration;
+pub mod combo;
+pub mod debounce;
+pub mod distance;
+pub mod echo;
+pub mod filter;
+pub mod freq;
+pub mod gpiote;
+pub mod keypad;
+pub mod latency;
+pub mod leds;
+pub mod logic;
+pub mod matrix;
+pub mod melody;
 pub mod mono;
+pub mod morse;
+pub mod pattern;
+pub mod port;
+pub mod pwm_in;
+pub mod qdec;
+pub mod range;
+pub mod rgb;
+pub mod ring;
+pub mod srf04;
+pub mod stats;
+pub mod velocity;
+pub mod vl53l0x;
+pub mod vl53l1x;
+pub mod ws2812;
+pub mod zone;
 
 use panic_probe as _;
 
@@ -0,0 +1,116 @@
+// Edge recorder for up to eight pins
+//
+// Every pin gets a GPIOTE channel firing on both edges. The interrupt samples all pins at once and
+// stores the levels with the monotonic time in a ring buffer, to be drained at leisure. The
+// `print_*` functions stream the samples in the format the `la2vcd` host tool turns into a VCD
+// file. Timestamps carry the interrupt latency, around a microsecond, and edges closer together
+// than that may be merged.
+//
+// Timestamps are 32-bit ticks, which wrap every 67 s at 64 MHz. `la2vcd` counts the wraps, so the
+// app has to print something at least that often, see `print_heartbeat`.
+use crate::{
+    gpiote::{channel, NUM_CHANNELS},
+    ring::Ring,
+};
+use nrf52840_hal::{
+    gpio::{Floating, Input, Pin},
+    gpiote::Gpiote,
+    prelude::*,
+};
+
+/// Samples buffered until they're popped
+pub const CAPACITY: usize = 256;
+
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub struct Sample {
+    /// Monotonic ticks
+    pub at: u32,
+    /// Pin `i` is bit `i`
+    pub levels: u8,
+}
+
+pub struct LogicCapture<const N: usize> {
+    pins: [Pin<Input<Floating>>; N],
+    gpiote: Gpiote,
+    ring: Ring<Sample, CAPACITY>,
+    levels: u8,
+}
+
+impl<const N: usize> LogicCapture<N> {
+    /// Binds `pins` to GPIOTE channels `0..N`
+    pub fn new(pins: [Pin<Input<Floating>>; N], gpiote: Gpiote) -> Self {
+        assert!(N <= NUM_CHANNELS, "at most 8 pins are supported");
+        for (ch, pin) in pins.iter().enumerate() {
+            channel(&gpiote, ch)
+                .input_pin(pin)
+                .toggle()
+                .enable_interrupt();
+        }
+        let mut capture = LogicCapture {
+            pins,
+            gpiote,
+            ring: Ring::new(),
+            levels: 0,
+        };
+        capture.levels = capture.read();
+        capture
+    }
+
+    fn read(&self) -> u8 {
+        self.pins.iter().enumerate().fold(0, |levels, (i, pin)| {
+            levels | (pin.is_high().unwrap() as u8) << i
+        })
+    }
+
+    /// Current levels of all pins
+    pub fn levels(&self) -> u8 {
+        self.levels
+    }
+
+    /// Handles the GPIOTE interrupt, `now` in monotonic ticks
+    pub fn on_gpiote(&mut self, now: u32) {
+        self.gpiote.reset_events();
+        let levels = self.read();
+        if levels != self.levels {
+            self.levels = levels;
+            self.ring.push(Sample { at: now, levels });
+        }
+    }
+
+    /// Oldest buffered sample
+    pub fn pop(&mut self) -> Option<Sample> {
+        self.ring.pop()
+    }
+
+    /// Samples lost to a full buffer since the last call
+    pub fn take_dropped(&mut self) -> u32 {
+        self.ring.take_dropped()
+    }
+}
+
+/// Prints the stream header, `names` label the pins in the VCD file
+pub fn print_header(freq: u32, names: &[&str], now: u32, levels: u8) {
+    defmt::info!("la: start {=u32} {=usize}", freq, names.len());
+    for (i, name) in names.iter().enumerate() {
+        defmt::info!("la: pin {=usize} {=str}", i, name);
+    }
+    defmt::info!("la: {=u32} {=u8}", now, levels);
+}
+
+/// Prints samples in the format `la2vcd` reads
+pub fn print_sample(sample: Sample) {
+    defmt::info!("la: {=u32} {=u8}", sample.at, sample.levels);
+}
+
+/// Prints the current time, for `la2vcd` to keep count of timestamp wraps while the pins are idle
+///
+/// `now` must be no older than any sample still to be printed, so take it with the capture locked
+/// and the buffer empty.
+pub fn print_heartbeat(now: u32) {
+    defmt::info!("la: now {=u32}", now);
+}
+
+/// Prints a gap marker, `la2vcd` reports it
+pub fn print_dropped(count: u32) {
+    defmt::info!("la: dropped {=u32}", count);
+}
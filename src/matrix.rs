@@ -0,0 +1,125 @@
+// Key matrix state with per-key debouncing and ghosting detection
+//
+// Fed with raw scans of an `R` x `C` matrix, key `row * C + col` in bit `row * C + col`. A key
+// changes once its raw state has held for `debounce`, and the change is dated from when it began.
+//
+// Without diodes, three keys on the corners of a rectangle make the fourth corner read as pressed
+// too, and there's no telling which of the four are really down. A scan with two rows sharing two
+// or more pressed columns is flagged as ghosting and leaves the keys as they were, until the
+// rectangle is gone again.
+use crate::ring::Ring;
+use fugit::{TimerDurationU32, TimerInstantU32};
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub struct Key {
+    pub row: u8,
+    pub col: u8,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct KeyEvent<const FREQ: u32> {
+    pub key: Key,
+    pub pressed: bool,
+    pub at: TimerInstantU32<FREQ>,
+}
+
+pub struct KeyMatrix<const R: usize, const C: usize, const FREQ: u32> {
+    debounce: TimerDurationU32<FREQ>,
+    // Debounced keys
+    pressed: u32,
+    // Keys whose raw state differs from `pressed`, since when
+    changing: u32,
+    since: [TimerInstantU32<FREQ>; 32],
+    ghosting: bool,
+    events: Ring<KeyEvent<FREQ>, 16>,
+}
+
+impl<const R: usize, const C: usize, const FREQ: u32> KeyMatrix<R, C, FREQ> {
+    const COLS: u32 = (1 << C) - 1;
+
+    pub fn new(debounce: TimerDurationU32<FREQ>) -> Self {
+        assert!(R * C <= 32, "at most 32 keys are supported");
+        KeyMatrix {
+            debounce,
+            pressed: 0,
+            changing: 0,
+            since: [TimerInstantU32::from_ticks(0); 32],
+            ghosting: false,
+            events: Ring::new(),
+        }
+    }
+
+    /// Feeds a raw scan, bit set for a closed key
+    pub fn update(&mut self, raw: u32, now: TimerInstantU32<FREQ>) {
+        self.ghosting = self.has_ghost(raw);
+        if self.ghosting {
+            self.changing = 0;
+            return;
+        }
+
+        let differs = raw ^ self.pressed;
+        for k in bits(differs & !self.changing) {
+            self.since[k] = now;
+        }
+        self.changing = differs;
+
+        for k in bits(self.changing) {
+            if now - self.since[k] >= self.debounce {
+                self.pressed ^= 1 << k;
+                self.changing &= !(1 << k);
+                self.events.push(KeyEvent {
+                    key: Key {
+                        row: (k / C) as u8,
+                        col: (k % C) as u8,
+                    },
+                    pressed: self.pressed & 1 << k != 0,
+                    at: self.since[k],
+                });
+            }
+        }
+    }
+
+    fn row(raw: u32, row: usize) -> u32 {
+        raw >> (row * C) & Self::COLS
+    }
+
+    fn has_ghost(&self, raw: u32) -> bool {
+        (0..R)
+            .any(|a| (a + 1..R).any(|b| (Self::row(raw, a) & Self::row(raw, b)).count_ones() >= 2))
+    }
+
+    /// Next event, oldest first
+    pub fn event(&mut self) -> Option<KeyEvent<FREQ>> {
+        self.events.pop()
+    }
+
+    /// Debounced keys, same layout as the scans
+    pub fn pressed(&self) -> u32 {
+        self.pressed
+    }
+
+    pub fn is_pressed(&self, key: Key) -> bool {
+        self.pressed & 1 << (key.row as usize * C + key.col as usize) != 0
+    }
+
+    /// The last scan was ambiguous and got ignored
+    pub fn is_ghosting(&self) -> bool {
+        self.ghosting
+    }
+
+    /// No keys down or changing, scanning can stop
+    pub fn is_idle(&self) -> bool {
+        self.pressed == 0 && self.changing == 0 && !self.ghosting && self.events.is_empty()
+    }
+}
+
+// Indices of the set bits
+fn bits(mut mask: u32) -> impl Iterator<Item = usize> {
+    core::iter::from_fn(move || {
+        let k = mask.trailing_zeros() as usize;
+        (mask != 0).then(|| {
+            mask &= mask - 1;
+            k
+        })
+    })
+}
@@ -0,0 +1,269 @@
+// Tones, melody strings and the choice of what a buzzer plays
+//
+// Melodies are written as space separated notes: a letter `a`-`g`, an optional `#` or `b`, an
+// optional octave `1`-`8` (4 by default), an optional length `/1` to `/32` (quarter notes by
+// default) and an optional `.` for dotted notes. `r` is a rest. "c e g c5/2" is a C major
+// arpeggio ending on a half note. Notes are cut a little short so repeated ones stay distinct.
+//
+// A `Player` holds what's playing and at which priority. A sound only starts if nothing of a
+// higher priority is playing, and replaces whatever was there, so alarms aren't cut by UI beeps.
+
+/// Silence between melody notes, taken off the note
+pub const GAP_MS: u16 = 15;
+
+// Octave 4 in 1/100 Hz, from C
+const OCTAVE_4: [u32; 12] = [
+    26163, 27718, 29366, 31113, 32963, 34923, 36999, 39200, 41530, 44000, 46616, 49388,
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub struct Tone {
+    /// Zero for a rest
+    pub hz: u16,
+    pub ms: u16,
+}
+
+impl Tone {
+    pub const fn new(hz: u16, ms: u16) -> Self {
+        Tone { hz, ms }
+    }
+
+    pub const fn rest(ms: u16) -> Self {
+        Tone { hz: 0, ms }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub enum Error {
+    /// The note starting at this byte offset couldn't be read
+    BadNote(usize),
+}
+
+/// Frequency of `semitone` (0 is C) in `octave`, rounded
+pub fn note_hz(semitone: u8, octave: u8) -> u16 {
+    (((OCTAVE_4[semitone as usize] << octave) / 16 + 50) / 100) as u16
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub struct Melody {
+    pub notes: &'static str,
+    pub bpm: u16,
+}
+
+impl Melody {
+    pub const fn new(notes: &'static str, bpm: u16) -> Self {
+        Melody { notes, bpm }
+    }
+
+    pub fn tones(&self) -> Tones {
+        Tones {
+            notes: self.notes,
+            pos: 0,
+            whole_ms: 4 * 60_000 / self.bpm.max(1) as u32,
+            gap: 0,
+        }
+    }
+
+    /// Reads the whole melody, for the first note that's wrong
+    pub fn check(&self) -> Result<(), Error> {
+        self.tones().try_for_each(|t| t.map(|_| ()))
+    }
+}
+
+/// The tones of a melody, gaps included
+pub struct Tones {
+    notes: &'static str,
+    pos: usize,
+    whole_ms: u32,
+    // Gap owed after the last note
+    gap: u16,
+}
+
+impl Iterator for Tones {
+    type Item = Result<Tone, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.gap > 0 {
+            return Some(Ok(Tone::rest(core::mem::take(&mut self.gap))));
+        }
+        let rest = &self.notes[self.pos..];
+        let start = self.pos + (rest.len() - rest.trim_start().len());
+        let note = self.notes[start..].split_whitespace().next()?;
+        self.pos = start + note.len();
+        match parse(note, self.whole_ms) {
+            Some(tone) if tone.hz == 0 => Some(Ok(tone)),
+            Some(tone) => {
+                self.gap = GAP_MS.min(tone.ms / 2);
+                Some(Ok(Tone::new(tone.hz, tone.ms - self.gap)))
+            }
+            None => {
+                // Nothing after a bad note
+                self.pos = self.notes.len();
+                Some(Err(Error::BadNote(start)))
+            }
+        }
+    }
+}
+
+fn parse(note: &str, whole_ms: u32) -> Option<Tone> {
+    let mut chars = note.chars().peekable();
+    let semitone = match chars.next()?.to_ascii_lowercase() {
+        'r' => None,
+        'c' => Some(0),
+        'd' => Some(2),
+        'e' => Some(4),
+        'f' => Some(5),
+        'g' => Some(7),
+        'a' => Some(9),
+        'b' => Some(11),
+        _ => return None,
+    };
+    let mut index = semitone.map(|s: i32| s + 4 * 12);
+    if let Some(i) = index.as_mut() {
+        match chars.peek().copied() {
+            Some('#') => {
+                *i += 1;
+                chars.next();
+            }
+            Some('b') => {
+                *i -= 1;
+                chars.next();
+            }
+            _ => {}
+        }
+        if let Some(octave) = chars.peek().and_then(|c| c.to_digit(10)) {
+            if !(1..=8).contains(&octave) {
+                return None;
+            }
+            *i += (octave as i32 - 4) * 12;
+            chars.next();
+        }
+    }
+
+    let mut ms = whole_ms / 4;
+    if chars.peek() == Some(&'/') {
+        chars.next();
+        let mut len = 0;
+        while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+            len = len * 10 + d;
+            chars.next();
+        }
+        if !matches!(len, 1 | 2 | 4 | 8 | 16 | 32) {
+            return None;
+        }
+        ms = whole_ms / len;
+    }
+    if chars.peek() == Some(&'.') {
+        chars.next();
+        ms = ms * 3 / 2;
+    }
+    if chars.next().is_some() {
+        return None;
+    }
+
+    let ms = ms.min(u16::MAX as u32) as u16;
+    Some(match index {
+        None => Tone::rest(ms),
+        Some(i) if i < 12 => return None,
+        Some(i) => Tone::new(note_hz((i % 12) as u8, (i / 12) as u8), ms),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub enum Sound {
+    Tone(Tone),
+    Melody(Melody),
+}
+
+enum Steps {
+    Tone(Option<Tone>),
+    Melody(Tones),
+}
+
+struct Playing {
+    priority: u8,
+    sound: Sound,
+    repeat: bool,
+    steps: Steps,
+}
+
+/// What's playing, a tone at a time
+pub struct Player {
+    playing: Option<Playing>,
+}
+
+impl Player {
+    pub const fn new() -> Self {
+        Player { playing: None }
+    }
+
+    /// Starts `sound` at `priority`, over and over with `repeat` until stopped
+    ///
+    /// Returns `false` if something of a higher priority is playing.
+    pub fn play(&mut self, priority: u8, sound: Sound, repeat: bool) -> bool {
+        if self.priority().is_some_and(|p| p > priority) {
+            return false;
+        }
+        self.playing = Some(Playing {
+            priority,
+            sound,
+            repeat,
+            steps: steps(sound),
+        });
+        true
+    }
+
+    /// Stops what's playing if it was started at `priority`
+    pub fn stop(&mut self, priority: u8) -> bool {
+        if self.priority() == Some(priority) {
+            self.playing = None;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Priority of what's playing
+    pub fn priority(&self) -> Option<u8> {
+        self.playing.as_ref().map(|p| p.priority)
+    }
+
+    /// The tone to play next, `None` once done
+    ///
+    /// A melody with a bad note ends there, repeating or not.
+    pub fn next_tone(&mut self) -> Option<Tone> {
+        let playing = self.playing.as_mut()?;
+        let mut restarted = false;
+        loop {
+            let tone = match &mut playing.steps {
+                Steps::Tone(tone) => tone.take().map(Ok),
+                Steps::Melody(tones) => tones.next(),
+            };
+            match tone {
+                Some(Ok(tone)) => return Some(tone),
+                // An empty melody would restart forever
+                None if playing.repeat && !restarted => {
+                    playing.steps = steps(playing.sound);
+                    restarted = true;
+                }
+                _ => {
+                    self.playing = None;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+impl Default for Player {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn steps(sound: Sound) -> Steps {
+    match sound {
+        Sound::Tone(tone) => Steps::Tone(Some(tone)),
+        Sound::Melody(melody) => Steps::Melody(melody.tones()),
+    }
+}
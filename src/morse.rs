@@ -0,0 +1,155 @@
+// Morse decoder working from press and gap lengths
+//
+// Timing is in units of one dot: a dash is three, the gap inside a letter one, between letters
+// three and between words seven. Presses are split into dots and dashes at two units, and every
+// press nudges the unit estimate, so the decoder follows the sender's speed. Silence longer than
+// two units ends the letter and longer than five the word.
+use crate::ring::Ring;
+
+// Unit limits, 60 and 3 WPM
+const MIN_UNIT_MS: u32 = 20;
+const MAX_UNIT_MS: u32 = 400;
+// Longest code in the table
+const MAX_ELEMENTS: usize = 7;
+
+const CODES: [(char, &str); 47] = [
+    ('A', ".-"),
+    ('B', "-..."),
+    ('C', "-.-."),
+    ('D', "-.."),
+    ('E', "."),
+    ('F', "..-."),
+    ('G', "--."),
+    ('H', "...."),
+    ('I', ".."),
+    ('J', ".---"),
+    ('K', "-.-"),
+    ('L', ".-.."),
+    ('M', "--"),
+    ('N', "-."),
+    ('O', "---"),
+    ('P', ".--."),
+    ('Q', "--.-"),
+    ('R', ".-."),
+    ('S', "..."),
+    ('T', "-"),
+    ('U', "..-"),
+    ('V', "...-"),
+    ('W', ".--"),
+    ('X', "-..-"),
+    ('Y', "-.--"),
+    ('Z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    ('?', "..--.."),
+    ('\'', ".----."),
+    ('/', "-..-."),
+    ('(', "-.--."),
+    (')', "-.--.-"),
+    (':', "---..."),
+    ('=', "-...-"),
+    ('+', ".-.-."),
+    ('-', "-....-"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub enum Element {
+    Dot,
+    Dash,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Gap {
+    // Within a letter, or nothing sent yet
+    Element,
+    Letter,
+    Word,
+}
+
+pub struct Decoder {
+    unit: u32,
+    elements: [u8; MAX_ELEMENTS],
+    len: usize,
+    // Set when the letter has more elements than any code
+    overflow: bool,
+    gap: Gap,
+    text: Ring<char, 8>,
+}
+
+impl Decoder {
+    /// Starts out expecting `wpm` words per minute
+    pub fn new(wpm: u32) -> Self {
+        Decoder {
+            unit: (1200 / wpm.max(1)).clamp(MIN_UNIT_MS, MAX_UNIT_MS),
+            elements: [0; MAX_ELEMENTS],
+            len: 0,
+            overflow: false,
+            gap: Gap::Word,
+            text: Ring::new(),
+        }
+    }
+
+    /// Feeds the length of a press in ms
+    pub fn press(&mut self, ms: u32) -> Element {
+        let (element, units) = if ms >= 2 * self.unit {
+            (Element::Dash, 3)
+        } else {
+            (Element::Dot, 1)
+        };
+        self.unit = ((3 * self.unit + ms / units + 2) / 4).clamp(MIN_UNIT_MS, MAX_UNIT_MS);
+
+        match self.elements.get_mut(self.len) {
+            Some(slot) => {
+                *slot = if element == Element::Dash { b'-' } else { b'.' };
+                self.len += 1;
+            }
+            None => self.overflow = true,
+        }
+        self.gap = Gap::Element;
+        element
+    }
+
+    /// Feeds the time since the last release in ms, at the next press and while waiting for it
+    pub fn silence(&mut self, ms: u32) {
+        if self.gap == Gap::Element && self.len > 0 && ms >= 2 * self.unit {
+            let code = &self.elements[..self.len];
+            let letter = CODES
+                .iter()
+                .find(|(_, c)| c.as_bytes() == code && !self.overflow)
+                .map_or('?', |&(letter, _)| letter);
+            self.text.push(letter);
+            self.len = 0;
+            self.overflow = false;
+            self.gap = Gap::Letter;
+        }
+        if self.gap == Gap::Letter && ms >= 5 * self.unit {
+            self.text.push(' ');
+            self.gap = Gap::Word;
+        }
+    }
+
+    /// Next decoded character, unknown codes come out as `?`
+    pub fn next_char(&mut self) -> Option<char> {
+        self.text.pop()
+    }
+
+    /// Speed estimate
+    pub fn wpm(&self) -> u32 {
+        1200 / self.unit
+    }
+
+    /// Nothing left for `silence` to finish off
+    pub fn is_idle(&self) -> bool {
+        self.gap == Gap::Word && self.text.is_empty()
+    }
+}
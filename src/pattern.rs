@@ -0,0 +1,210 @@
+// LED patterns, as brightness levels in fixed time steps
+//
+// A pattern is a loop of `steps()` steps of `STEP_MS` each, `level(step)` giving the brightness in
+// that step. Every LED can have a few patterns requested at different priorities in its `Layers`,
+// the highest one is shown.
+//
+// Patterns for several LEDs are played as one frame, as long as the least common multiple of their
+// lengths so they all loop cleanly. Frames that would be longer than `MAX_STEPS` are cut at the
+// longest pattern instead, and the shorter ones restart early at the end of the frame.
+
+pub const STEP_MS: u32 = 20;
+pub const MAX_STEPS: usize = 512;
+
+// Flash timings, in steps
+const FLASH_ON: usize = 10;
+const FLASH_OFF: usize = 10;
+const DIGIT_PAUSE: usize = 30;
+const CODE_PAUSE: usize = 70;
+const LONG_FLASH: usize = 50;
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub enum Pattern {
+    Off,
+    /// Steady, at this brightness
+    On(u8),
+    Blink {
+        on_ms: u16,
+        off_ms: u16,
+    },
+    /// Fades in and out over `period_ms`
+    Breathe {
+        period_ms: u16,
+    },
+    /// A strong and a weak beat at this many beats per minute
+    Heartbeat {
+        bpm: u8,
+    },
+    /// `count` flashes and a pause, for error codes
+    Flashes {
+        count: u8,
+    },
+    /// Two digit blink code, the tens and then the units as flashes, zero as a long one
+    Code(u8),
+}
+
+impl Pattern {
+    pub fn steps(&self) -> usize {
+        match *self {
+            Pattern::Off | Pattern::On(_) => 1,
+            Pattern::Blink { on_ms, off_ms } => steps(on_ms as u32) + steps(off_ms as u32),
+            Pattern::Breathe { period_ms } => steps(period_ms as u32).max(2),
+            Pattern::Heartbeat { bpm } => steps(60_000 / bpm.max(1) as u32).max(16),
+            Pattern::Flashes { count } => {
+                count as usize * (FLASH_ON + FLASH_OFF) + CODE_PAUSE - FLASH_OFF
+            }
+            Pattern::Code(code) => {
+                digit_steps(code / 10 % 10) + DIGIT_PAUSE + digit_steps(code % 10) + CODE_PAUSE
+            }
+        }
+        .min(MAX_STEPS)
+    }
+
+    /// Brightness in `step`, which wraps around
+    pub fn level(&self, step: usize) -> u8 {
+        let step = step % self.steps();
+        match *self {
+            Pattern::Off => 0,
+            Pattern::On(level) => level,
+            Pattern::Blink { on_ms, .. } => on_if(step < steps(on_ms as u32)),
+            Pattern::Breathe { .. } => {
+                // Triangle, squared to look even to the eye
+                let n = self.steps() as u32;
+                let up = (2 * step as u32).min(2 * (n - step as u32));
+                let linear = up * 255 / n;
+                (linear * linear / 255) as u8
+            }
+            Pattern::Heartbeat { .. } => match step {
+                0..=4 => 255,
+                10..=13 => 96,
+                _ => 0,
+            },
+            Pattern::Flashes { count } => on_if(
+                step < count as usize * (FLASH_ON + FLASH_OFF)
+                    && step % (FLASH_ON + FLASH_OFF) < FLASH_ON,
+            ),
+            Pattern::Code(code) => {
+                let tens = digit_steps(code / 10 % 10);
+                if step < tens {
+                    digit_level(code / 10 % 10, step)
+                } else if step >= tens + DIGIT_PAUSE {
+                    digit_level(code % 10, step - tens - DIGIT_PAUSE)
+                } else {
+                    0
+                }
+            }
+        }
+    }
+}
+
+fn steps(ms: u32) -> usize {
+    ((ms + STEP_MS / 2) / STEP_MS).max(1) as usize
+}
+
+fn on_if(on: bool) -> u8 {
+    if on {
+        255
+    } else {
+        0
+    }
+}
+
+// A digit's flashes without the trailing gap
+fn digit_steps(digit: u8) -> usize {
+    match digit {
+        0 => LONG_FLASH,
+        n => n as usize * (FLASH_ON + FLASH_OFF) - FLASH_OFF,
+    }
+}
+
+fn digit_level(digit: u8, step: usize) -> u8 {
+    match digit {
+        0 => on_if(step < LONG_FLASH),
+        _ => on_if(step < digit_steps(digit) && step % (FLASH_ON + FLASH_OFF) < FLASH_ON),
+    }
+}
+
+/// Length of the frame playing all of `patterns` together
+pub fn frame_steps(patterns: &[Pattern]) -> usize {
+    let lcm = patterns.iter().try_fold(1, |lcm, p| {
+        let n = p.steps();
+        let lcm = lcm / gcd(lcm, n) * n;
+        (lcm <= MAX_STEPS).then_some(lcm)
+    });
+    lcm.unwrap_or_else(|| patterns.iter().map(Pattern::steps).max().unwrap_or(1))
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// Patterns requested for one LED, by priority
+pub struct Layers<const N: usize> {
+    slots: [Option<(u8, Pattern)>; N],
+}
+
+impl<const N: usize> Layers<N> {
+    pub const fn new() -> Self {
+        Layers { slots: [None; N] }
+    }
+
+    /// Requests `pattern` at `priority`, replacing what was there at the same priority
+    ///
+    /// With all slots taken, the lowest priority is dropped if it's below `priority`. Returns
+    /// `false` if the pattern didn't make it in.
+    pub fn set(&mut self, priority: u8, pattern: Pattern) -> bool {
+        let slot = match self
+            .slots
+            .iter()
+            .position(|s| matches!(s, Some((p, _)) if *p == priority))
+        {
+            Some(i) => i,
+            None => match self.slots.iter().position(Option::is_none) {
+                Some(i) => i,
+                None => {
+                    let (i, lowest) = self
+                        .slots
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, s)| s.map(|(p, _)| (i, p)))
+                        .min_by_key(|&(_, p)| p)
+                        .unwrap();
+                    if lowest > priority {
+                        return false;
+                    }
+                    i
+                }
+            },
+        };
+        self.slots[slot] = Some((priority, pattern));
+        true
+    }
+
+    /// Withdraws the pattern at `priority`
+    pub fn clear(&mut self, priority: u8) {
+        for slot in self.slots.iter_mut() {
+            if matches!(slot, Some((p, _)) if *p == priority) {
+                *slot = None;
+            }
+        }
+    }
+
+    /// The pattern to show, `Off` when nothing is requested
+    pub fn current(&self) -> Pattern {
+        self.slots
+            .iter()
+            .flatten()
+            .max_by_key(|(p, _)| *p)
+            .map_or(Pattern::Off, |&(_, pattern)| pattern)
+    }
+}
+
+impl<const N: usize> Default for Layers<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,123 @@
+// Pin change detection through the GPIOTE PORT event
+//
+// Watches any number of pins without using up GPIOTE channels, leaving those for the jobs that need
+// exact timing. Every pin senses the level opposite to its current one and the ports run in
+// latched detect mode, so each pin that changed keeps its LATCH bit until the interrupt has seen
+// it. After a change the sense is flipped to catch the way back; if the pin already moved again
+// its LATCH bit sets right away and a new PORT event follows.
+//
+// A pin can show up as changed while still at its old level, when a pulse was shorter than the
+// interrupt latency. Buttons should go through `button::Button` for debouncing anyway.
+use nrf52840_hal::{
+    gpio::{Input, Pin, Port},
+    gpiote::Gpiote,
+    pac::{p0, GPIOTE, P0, P1},
+    prelude::*,
+};
+
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub struct Change {
+    /// Levels after the change, pin `i` is bit `i`
+    pub levels: u32,
+    /// Pins that changed at least once since the last event
+    pub changed: u32,
+}
+
+impl Change {
+    pub fn rose(&self) -> u32 {
+        self.changed & self.levels
+    }
+
+    pub fn fell(&self) -> u32 {
+        self.changed & !self.levels
+    }
+}
+
+pub struct PortWatch<MODE, const N: usize> {
+    pins: [Pin<Input<MODE>>; N],
+    levels: u32,
+}
+
+impl<MODE, const N: usize> PortWatch<MODE, N> {
+    const ALL: u32 = ((1u64 << N) - 1) as u32;
+
+    /// Starts watching `pins` and enables the PORT interrupt
+    pub fn new(pins: [Pin<Input<MODE>>; N], gpiote: &Gpiote) -> Self {
+        assert!(N <= 32, "at most 32 pins are supported");
+        let mut watch = PortWatch { pins, levels: 0 };
+        for pin in watch.pins.iter() {
+            regs(pin).detectmode.write(|w| w.detectmode().ldetect());
+        }
+        watch.levels = watch.read();
+        watch.arm(Self::ALL);
+        gpiote.port().enable_interrupt();
+        watch
+    }
+
+    /// Samples the pins now, pin `i` is bit `i`
+    pub fn read(&self) -> u32 {
+        self.pins.iter().enumerate().fold(0, |levels, (i, pin)| {
+            levels | (pin.is_high().unwrap() as u32) << i
+        })
+    }
+
+    // Senses the opposite of the last read level, then clears the latch
+    fn arm(&self, mask: u32) {
+        for (i, pin) in self.pins.iter().enumerate() {
+            if mask & 1 << i == 0 {
+                continue;
+            }
+            let regs = regs(pin);
+            regs.pin_cnf[pin.pin() as usize].modify(|_, w| {
+                if self.levels & 1 << i != 0 {
+                    w.sense().low()
+                } else {
+                    w.sense().high()
+                }
+            });
+            regs.latch.write(|w| unsafe { w.bits(1 << pin.pin()) });
+        }
+    }
+
+    /// Stops sensing, for while the pins are being driven through other means
+    pub fn pause(&self) {
+        for pin in self.pins.iter() {
+            let regs = regs(pin);
+            regs.pin_cnf[pin.pin() as usize].modify(|_, w| w.sense().disabled());
+            regs.latch.write(|w| unsafe { w.bits(1 << pin.pin()) });
+        }
+    }
+
+    /// Senses again, against the levels the pins have now
+    pub fn resume(&mut self) {
+        self.levels = self.read();
+        self.arm(Self::ALL);
+    }
+
+    /// Current levels of all pins
+    pub fn levels(&self) -> u32 {
+        self.levels
+    }
+
+    /// Handles and clears the PORT event, the GPIOTE channel events are left to their users
+    pub fn on_port_event(&mut self) -> Change {
+        // Cleared before the latches are read, so a change from here on raises it again
+        unsafe { &*GPIOTE::ptr() }.events_port.write(|w| w);
+        let latched = self.pins.iter().enumerate().fold(0, |latched, (i, pin)| {
+            let bit = regs(pin).latch.read().bits() >> pin.pin() & 1;
+            latched | bit << i
+        });
+        let levels = self.read();
+        let changed = latched | (levels ^ self.levels);
+        self.levels = levels;
+        self.arm(changed);
+        Change { levels, changed }
+    }
+}
+
+fn regs<MODE>(pin: &Pin<MODE>) -> &'static p0::RegisterBlock {
+    match pin.port() {
+        Port::Port0 => unsafe { &*P0::ptr() },
+        Port::Port1 => unsafe { &*P1::ptr() },
+    }
+}
@@ -0,0 +1,149 @@
+// Period, high time and duty cycle of a digital signal
+//
+// Same scheme as `echo`: a free-running 16 MHz timer captures rising edges into CC[0] and falling
+// edges into CC[1] through PPI, so the edge times are exact and only the rising edge needs an
+// interrupt. On every rising edge the new CC[0] and the CC[1] in between are compared to the
+// previous rising edge.
+//
+// Pulses are returned from `on_rise` and, if one is set, passed to a callback. A non-capturing
+// closure that spawns an RTIC task, `|p| { report::spawn(p).ok(); }`, turns the task's queue into
+// a channel.
+use crate::mono::Instance32;
+use fugit::{MicrosDurationU32, TimerDurationU32};
+use nrf52840_hal::pac::timer0::TASKS_CAPTURE;
+
+pub const FREQ: u32 = 16_000_000;
+
+pub type Duration = TimerDurationU32<FREQ>;
+
+const CC_RISE: usize = 0;
+const CC_FALL: usize = 1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Pulse {
+    /// Rising edge to rising edge
+    pub period: Duration,
+    /// Rising edge to falling edge
+    pub high: Duration,
+}
+
+impl Pulse {
+    /// Duty cycle in 1/1000ths
+    pub fn duty_permille(&self) -> u32 {
+        (self.high.ticks() as u64 * 1_000 / self.period.ticks() as u64) as u32
+    }
+
+    pub fn frequency_hz(&self) -> u32 {
+        FREQ / self.period.ticks()
+    }
+}
+
+impl defmt::Format for Pulse {
+    fn format(&self, f: defmt::Formatter) {
+        let period: MicrosDurationU32 = self.period.convert();
+        let high: MicrosDurationU32 = self.high.convert();
+        let duty = self.duty_permille();
+        defmt::write!(
+            f,
+            "period {} us, high {} us, duty {}.{}%",
+            period.ticks(),
+            high.ticks(),
+            duty / 10,
+            duty % 10
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum Mode {
+    /// Measure every period
+    Continuous,
+    /// Measure one period after each `arm`
+    OneShot,
+}
+
+pub struct PwmInput<T: Instance32> {
+    timer: T,
+    mode: Mode,
+    callback: Option<fn(Pulse)>,
+    last_rise: Option<u32>,
+    armed: bool,
+}
+
+impl<T: Instance32> PwmInput<T> {
+    /// Starts `timer` free-running at 16 MHz, one-shot mode starts out disarmed
+    pub fn new(timer: T, mode: Mode) -> Self {
+        timer.prescaler.write(|w| unsafe { w.prescaler().bits(0) }); // 16 MHz
+        timer.bitmode.write(|w| w.bitmode()._32bit());
+        timer.mode.write(|w| w.mode().timer());
+        timer.shorts.reset();
+        timer.tasks_clear.write(|w| unsafe { w.bits(1) });
+        timer.tasks_start.write(|w| unsafe { w.bits(1) });
+        PwmInput {
+            timer,
+            mode,
+            callback: None,
+            last_rise: None,
+            armed: mode == Mode::Continuous,
+        }
+    }
+
+    /// Calls `callback` with every pulse, from the rising edge interrupt
+    pub fn set_callback(&mut self, callback: fn(Pulse)) {
+        self.callback = Some(callback);
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.armed = mode == Mode::Continuous;
+        self.last_rise = None;
+    }
+
+    /// Measures the next full period in one-shot mode
+    pub fn arm(&mut self) {
+        self.armed = true;
+        self.last_rise = None;
+    }
+
+    /// Capture task to connect to the rising edge event of the input pin
+    pub fn task_capture_rise(&self) -> &TASKS_CAPTURE {
+        &self.timer.tasks_capture[CC_RISE]
+    }
+
+    /// Capture task to connect to the falling edge event of the input pin
+    pub fn task_capture_fall(&self) -> &TASKS_CAPTURE {
+        &self.timer.tasks_capture[CC_FALL]
+    }
+
+    /// Handles the rising edge interrupt, returns the period that just ended
+    pub fn on_rise(&mut self) -> Option<Pulse> {
+        if !self.armed {
+            return None;
+        }
+        let rise = self.timer.cc[CC_RISE].read().bits();
+        let fall = self.timer.cc[CC_FALL].read().bits();
+        let last_rise = self.last_rise.replace(rise)?;
+
+        let period = rise.wrapping_sub(last_rise);
+        let high = fall.wrapping_sub(last_rise);
+        if period == 0 || high > period {
+            // No falling edge since the last rising edge, the signal is too fast to follow
+            return None;
+        }
+        let pulse = Pulse {
+            period: Duration::from_ticks(period),
+            high: Duration::from_ticks(high),
+        };
+        if self.mode == Mode::OneShot {
+            self.armed = false;
+        }
+        if let Some(callback) = self.callback {
+            callback(pulse);
+        }
+        Some(pulse)
+    }
+
+    pub fn free(self) -> T {
+        self.timer
+    }
+}
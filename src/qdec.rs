@@ -0,0 +1,142 @@
+// Rotary encoder through the QDEC peripheral
+//
+// QDEC samples the A and B pins, filters them and accumulates the steps in hardware, interrupting
+// once `report` samples with movement have been taken. Each report turns into a `Turn` event,
+// scaled up on fast turns when acceleration is on. The switch of a push-encoder goes through
+// `button::Button`, its events come out of the same `event` queue as the turns.
+use crate::button::{self, Button, Timings};
+use fugit::{MillisDurationU32, TimerInstantU32};
+use nrf52840_hal::{
+    gpio::{Input, Pin, PullUp},
+    pac::QDEC,
+    qdec::{NumSamples, Qdec, SamplePeriod},
+};
+
+#[derive(Clone, Copy)]
+pub struct Config {
+    pub sample_period: SamplePeriod,
+    /// QDEC's own input filter, for mechanical encoders
+    pub debounce: bool,
+    /// Samples with movement per report
+    pub report: NumSamples,
+    pub acceleration: Option<Acceleration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            sample_period: SamplePeriod::_1024us,
+            debounce: true,
+            report: NumSamples::_1smpl,
+            acceleration: None,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Acceleration {
+    /// Steps per second from which turns get scaled up, by the speed over this
+    pub from: u32,
+    /// Largest scaling factor
+    pub max_factor: u32,
+}
+
+impl Acceleration {
+    /// Factor for `steps` taken in `elapsed` ms
+    pub fn factor(&self, steps: u32, elapsed: u32) -> u32 {
+        let speed = steps * 1000 / elapsed.max(1);
+        (speed / self.from.max(1)).clamp(1, self.max_factor.max(1))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub enum Event {
+    /// Steps since the last turn event, positive clockwise
+    Turn(i32),
+    /// Push switch
+    Button(button::Event),
+}
+
+pub struct Encoder<const FREQ: u32> {
+    qdec: Qdec,
+    acceleration: Option<Acceleration>,
+    position: i32,
+    // Turned but not reported by `event` yet
+    turned: i32,
+    last_report: Option<TimerInstantU32<FREQ>>,
+    switch: Option<Button<FREQ>>,
+}
+
+impl<const FREQ: u32> Encoder<FREQ> {
+    /// Starts decoding and enables the QDEC interrupt
+    pub fn new(qdec: QDEC, a: Pin<Input<PullUp>>, b: Pin<Input<PullUp>>, config: Config) -> Self {
+        let qdec = Qdec::new(qdec, a, b, None, config.sample_period);
+        qdec.debounce(config.debounce)
+            .enable_interrupt(config.report)
+            .enable();
+        Encoder {
+            qdec,
+            acceleration: config.acceleration,
+            position: 0,
+            turned: 0,
+            last_report: None,
+            switch: None,
+        }
+    }
+
+    /// Adds a push switch, fed through `update_switch`
+    pub fn with_switch(mut self, timings: Timings<FREQ>) -> Self {
+        self.switch = Some(Button::new(timings));
+        self
+    }
+
+    /// Handles the QDEC interrupt
+    pub fn on_report(&mut self, now: TimerInstantU32<FREQ>) {
+        self.qdec.reset_events();
+        let steps = self.qdec.read() as i32;
+        let factor = match (self.acceleration, self.last_report) {
+            (Some(acceleration), Some(last)) => {
+                let elapsed: MillisDurationU32 = (now - last).convert();
+                acceleration.factor(steps.unsigned_abs(), elapsed.ticks()) as i32
+            }
+            _ => 1,
+        };
+        self.last_report = Some(now);
+        self.position = self.position.wrapping_add(steps * factor);
+        self.turned = self.turned.saturating_add(steps * factor);
+    }
+
+    /// Feeds the switch level, `true` when pressed
+    pub fn update_switch(&mut self, pressed: bool, now: TimerInstantU32<FREQ>) {
+        if let Some(switch) = self.switch.as_mut() {
+            switch.update(pressed, now);
+        }
+    }
+
+    /// The switch needs no more updates until its next edge
+    pub fn is_switch_idle(&self) -> bool {
+        self.switch.as_ref().is_none_or(|switch| switch.is_idle())
+    }
+
+    /// Next event, turns first
+    pub fn event(&mut self) -> Option<Event> {
+        if self.turned != 0 {
+            return Some(Event::Turn(core::mem::take(&mut self.turned)));
+        }
+        self.switch.as_mut()?.event().map(Event::Button)
+    }
+
+    /// Steps turned since start, with acceleration applied
+    pub fn position(&self) -> i32 {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: i32) {
+        self.position = position;
+    }
+
+    pub fn free(self) -> Qdec {
+        self.qdec.disable();
+        self.qdec
+    }
+}
@@ -0,0 +1,15 @@
+// Common interface of the ranging drivers
+//
+// Apps drive any sensor the same way: `start` a measurement, then poll `read` until it stops
+// returning `WouldBlock`, or `nb::block!` on it where busy waiting is fine.
+use crate::distance::Distance;
+
+pub trait RangeSensor {
+    type Error;
+
+    /// Starts a new measurement, discarding any result not read yet
+    fn start(&mut self) -> Result<(), Self::Error>;
+
+    /// Result of the last started measurement
+    fn read(&mut self) -> nb::Result<Distance, Self::Error>;
+}
@@ -0,0 +1,59 @@
+// RGB colours and the corrections between a frame buffer and LED brightness
+//
+// LEDs are linear in current but the eye isn't, so frame values go through `gamma` before they're
+// sent. `current_ma` estimates what a frame draws, to scale it down below a supply limit.
+
+/// Draw of one colour channel of a WS2812 at full brightness
+pub const MA_PER_CHANNEL: u32 = 20;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, defmt::Format)]
+pub struct Rgb {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb::new(0, 0, 0);
+    pub const WHITE: Rgb = Rgb::new(255, 255, 255);
+    pub const RED: Rgb = Rgb::new(255, 0, 0);
+    pub const GREEN: Rgb = Rgb::new(0, 255, 0);
+    pub const BLUE: Rgb = Rgb::new(0, 0, 255);
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Self {
+        Rgb { r, g, b }
+    }
+
+    /// Scaled by `level` / 255
+    pub fn scale(self, level: u8) -> Self {
+        let f = |c: u8| (c as u32 * level as u32 / 255) as u8;
+        Rgb::new(f(self.r), f(self.g), f(self.b))
+    }
+
+    /// Mix of `self` and `other`, all `other` at 255
+    pub fn mix(self, other: Rgb, t: u8) -> Self {
+        let f = |a: u8, b: u8| ((a as u32 * (255 - t as u32) + b as u32 * t as u32) / 255) as u8;
+        Rgb::new(f(self.r, other.r), f(self.g, other.g), f(self.b, other.b))
+    }
+
+    /// With `gamma` applied to every channel
+    pub fn gamma(self) -> Self {
+        Rgb::new(gamma(self.r), gamma(self.g), gamma(self.b))
+    }
+}
+
+/// Perceived to LED brightness, a gamma of about 2.4
+pub fn gamma(x: u8) -> u8 {
+    let x = x as u32;
+    // Halfway between x^2 and x^3
+    ((x * x * 255 + x * x * x) / (2 * 255 * 255)) as u8
+}
+
+/// Estimated draw of `frame` in mA, with values as sent to the LEDs
+pub fn current_ma(frame: impl IntoIterator<Item = Rgb>) -> u32 {
+    let sum: u32 = frame
+        .into_iter()
+        .map(|c| c.r as u32 + c.g as u32 + c.b as u32)
+        .sum();
+    sum * MA_PER_CHANNEL / 255
+}
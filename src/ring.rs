@@ -0,0 +1,63 @@
+// Fixed-size ring buffer that overwrites the oldest entry when full
+//
+// For logging from interrupts: the producer never blocks, and the consumer learns how much it
+// missed from `dropped`.
+
+pub struct Ring<T: Copy, const N: usize> {
+    buf: [Option<T>; N],
+    // Next slot to write
+    head: usize,
+    len: usize,
+    dropped: u32,
+}
+
+impl<T: Copy, const N: usize> Ring<T, N> {
+    pub const fn new() -> Self {
+        Ring {
+            buf: [None; N],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Appends `value`, overwriting the oldest entry if the ring is full
+    pub fn push(&mut self, value: T) {
+        self.buf[self.head] = Some(value);
+        self.head = (self.head + 1) % N;
+        if self.len == N {
+            self.dropped = self.dropped.saturating_add(1);
+        } else {
+            self.len += 1;
+        }
+    }
+
+    /// Removes the oldest entry
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let tail = (self.head + N - self.len) % N;
+        self.len -= 1;
+        self.buf[tail].take()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Entries overwritten before they were popped, since the last call
+    pub fn take_dropped(&mut self) -> u32 {
+        core::mem::take(&mut self.dropped)
+    }
+}
+
+impl<T: Copy, const N: usize> Default for Ring<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
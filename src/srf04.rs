@@ -0,0 +1,181 @@
+// Driver for the SRF04/HC-SR04 ultrasonic ranger
+//
+// Also drives single-pin sensors like the Parallax PING or the SRF05 in mode 2, where the trigger
+// pulse is sent on the echo pin. The driver doesn't own a clock, the app passes in
+// `monotonics::now()` on every echo edge so it works with any `FREQ` monotonic.
+//
+// Readings are corrected with the calibration stored in flash, see `calibration`.
+use crate::{
+    calibration::Calibration, distance::Distance, gpiote::NUM_CHANNELS, range::RangeSensor,
+};
+use fugit::{MicrosDurationU32, TimerInstantU32};
+use nrf52840_hal::{
+    gpio::{Input, Level, Output, Pin, PullDown, PushPull},
+    pac::GPIOTE,
+    prelude::*,
+};
+
+/// Longest echo pulse accepted as a reading, ~4 m. The SRF04 raises a ~36 ms pulse when nothing
+/// is in range.
+pub const MAX_ECHO_US: u32 = 25_000;
+
+#[derive(Clone, Copy, PartialEq, defmt::Format)]
+pub enum Error {
+    /// No echo edge was seen before the measurement window closed
+    NoEcho,
+    /// The echo pulse was longer than `MAX_ECHO_US`
+    OutOfRange,
+}
+
+pub struct Srf04<const FREQ: u32> {
+    // `None` for single-pin sensors
+    trig_pin: Option<Pin<Output<PushPull>>>,
+    // Only `None` while a single-pin sensor is being triggered
+    echo_pin: Option<Pin<Input<PullDown>>>,
+    start: Option<TimerInstantU32<FREQ>>,
+    result: Option<Result<Distance, Error>>,
+    calibration: Calibration,
+}
+
+impl<const FREQ: u32> Srf04<FREQ> {
+    pub fn new(trig_pin: Pin<Output<PushPull>>, echo_pin: Pin<Input<PullDown>>) -> Self {
+        Srf04 {
+            trig_pin: Some(trig_pin),
+            echo_pin: Some(echo_pin),
+            start: None,
+            result: None,
+            calibration: Calibration::load().unwrap_or_default(),
+        }
+    }
+
+    /// Creates a driver for a sensor with a shared trigger/echo pin
+    pub fn single_pin(pin: Pin<Input<PullDown>>) -> Self {
+        Srf04 {
+            trig_pin: None,
+            echo_pin: Some(pin),
+            start: None,
+            result: None,
+            calibration: Calibration::load().unwrap_or_default(),
+        }
+    }
+
+    /// The pin to hook up to a GPIOTE channel, triggering on both edges
+    pub fn echo_pin(&self) -> &Pin<Input<PullDown>> {
+        self.echo_pin.as_ref().unwrap()
+    }
+
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    /// Replaces the calibration loaded from flash, e.g. `Calibration::IDENTITY` for raw readings
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
+    }
+
+    /// Sends a 10us trigger pulse, discarding any measurement in progress
+    pub fn trigger(&mut self) {
+        self.start = None;
+        self.result = None;
+        match self.trig_pin.as_mut() {
+            Some(trig_pin) => pulse(trig_pin),
+            None => {
+                let echo_pin = self.echo_pin.take().unwrap();
+                let parked = park_gpiote(&echo_pin);
+                let mut trig_pin = echo_pin.into_push_pull_output(Level::Low);
+                pulse(&mut trig_pin);
+                let echo_pin = trig_pin.into_pulldown_input();
+                unpark_gpiote(parked);
+                self.echo_pin = Some(echo_pin);
+            }
+        }
+    }
+
+    /// Handles an echo pin toggle, returns the calibrated distance once the falling edge is seen
+    pub fn on_edge(&mut self, now: TimerInstantU32<FREQ>) -> Option<Result<Distance, Error>> {
+        if self.echo_pin().is_high().unwrap() {
+            // Echo pulse started - store start time
+            self.start.replace(now);
+            None
+        } else {
+            // Echo pulse ended - calculate pulse duration
+            let t: MicrosDurationU32 = (now - self.start.take()?).convert();
+            let result = if t.ticks() > MAX_ECHO_US {
+                Err(Error::OutOfRange)
+            } else {
+                Ok(self.calibration.from_echo(t))
+            };
+            self.result = Some(result);
+            Some(result)
+        }
+    }
+
+    /// Gives up on a measurement in progress, `read` will return `Error::NoEcho`
+    pub fn cancel(&mut self) {
+        if self.result.is_none() {
+            self.result = Some(Err(Error::NoEcho));
+        }
+        self.start = None;
+    }
+
+    pub fn free(self) -> (Option<Pin<Output<PushPull>>>, Pin<Input<PullDown>>) {
+        (self.trig_pin, self.echo_pin.unwrap())
+    }
+}
+
+/// Edges still have to be fed in through `on_edge`
+impl<const FREQ: u32> RangeSensor for Srf04<FREQ> {
+    type Error = Error;
+
+    fn start(&mut self) -> Result<(), Error> {
+        self.trigger();
+        Ok(())
+    }
+
+    fn read(&mut self) -> nb::Result<Distance, Error> {
+        match self.result.take() {
+            Some(result) => result.map_err(nb::Error::Other),
+            None => Err(nb::Error::WouldBlock),
+        }
+    }
+}
+
+fn pulse(pin: &mut Pin<Output<PushPull>>) {
+    pin.set_high().ok();
+    cortex_m::asm::delay(640); // 10us
+    pin.set_low().ok();
+}
+
+const CONFIG_MODE_MASK: u32 = 0b11;
+const CONFIG_MODE_EVENT: u32 = 1;
+
+type Parked = [Option<u32>; NUM_CHANNELS];
+
+// A GPIOTE channel in event mode takes over its pin as an input, so any channel listening on
+// `pin` is disabled while the pin is driven. Returns the configs to restore with `unpark_gpiote`.
+fn park_gpiote<MODE>(pin: &Pin<MODE>) -> Parked {
+    // NOTE(unsafe) only the channels bound to our own pin are touched
+    let gpiote = unsafe { &*GPIOTE::ptr() };
+    let mut parked = [None; NUM_CHANNELS];
+    for (ch, config) in gpiote.config.iter().enumerate() {
+        let bits = config.read().bits();
+        let psel = (bits >> 8) & 0x3f; // PSEL + PORT
+        if bits & CONFIG_MODE_MASK == CONFIG_MODE_EVENT && psel == pin.psel_bits() {
+            config.write(|w| unsafe { w.bits(bits & !CONFIG_MODE_MASK) });
+            parked[ch] = Some(bits);
+        }
+    }
+    parked
+}
+
+fn unpark_gpiote(parked: Parked) {
+    // NOTE(unsafe) see `park_gpiote`
+    let gpiote = unsafe { &*GPIOTE::ptr() };
+    for (ch, bits) in parked.iter().enumerate() {
+        if let Some(bits) = *bits {
+            gpiote.config[ch].write(|w| unsafe { w.bits(bits) });
+            // The trigger pulse itself must not count as an echo edge
+            gpiote.events_in[ch].write(|w| w);
+        }
+    }
+}
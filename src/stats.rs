@@ -0,0 +1,125 @@
+// Running statistics over a stream of measurements
+//
+// Count, min, max, mean and variance, plus a histogram with `B` fixed-width buckets. Values below
+// the first bucket are counted in the first one and values past the last bucket in the last one.
+// Values are plain `u32`s, feed it micrometres, microseconds, milliseconds or whatever unit the
+// summary should be in.
+//
+// Mean and variance come from exact integer sums rather than Welford's running mean, on purpose:
+// the sums hold as many values as `count` can, need no FPU and lose nothing to rounding.
+
+pub struct Stats<const B: usize> {
+    count: u32,
+    min: u32,
+    max: u32,
+    sum: u64,
+    // Sum of squares, u32 squares need the room
+    sum_sq: u128,
+    lo: u32,
+    width: u32,
+    buckets: [u32; B],
+}
+
+impl<const B: usize> Stats<B> {
+    /// Bucket `i` holds values from `lo + i * width` up to, not including, `lo + (i + 1) * width`
+    pub const fn new(lo: u32, width: u32) -> Self {
+        assert!(B > 0 && width > 0);
+        Stats {
+            count: 0,
+            min: u32::MAX,
+            max: 0,
+            sum: 0,
+            sum_sq: 0,
+            lo,
+            width,
+            buckets: [0; B],
+        }
+    }
+
+    pub fn feed(&mut self, x: u32) {
+        self.count = self.count.saturating_add(1);
+        self.min = self.min.min(x);
+        self.max = self.max.max(x);
+
+        self.sum += x as u64;
+        self.sum_sq += x as u128 * x as u128;
+
+        let i = (x.saturating_sub(self.lo) / self.width) as usize;
+        let bucket = &mut self.buckets[i.min(B - 1)];
+        *bucket = bucket.saturating_add(1);
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.lo, self.width);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn min(&self) -> Option<u32> {
+        (self.count > 0).then_some(self.min)
+    }
+
+    pub fn max(&self) -> Option<u32> {
+        (self.count > 0).then_some(self.max)
+    }
+
+    /// Mean, rounded
+    pub fn mean(&self) -> Option<u32> {
+        let n = self.count as u64;
+        (n > 0).then(|| ((self.sum + n / 2) / n) as u32)
+    }
+
+    /// Sample variance, rounded down, needs at least two values
+    pub fn variance(&self) -> Option<u64> {
+        let n = self.count as u128;
+        let sum = self.sum as u128;
+        (n > 1).then(|| ((self.sum_sq - sum * sum / n) / (n - 1)) as u64)
+    }
+
+    /// Sample standard deviation, rounded down
+    pub fn std_dev(&self) -> Option<u32> {
+        self.variance().map(|v| isqrt(v) as u32)
+    }
+
+    pub fn histogram(&self) -> &[u32; B] {
+        &self.buckets
+    }
+
+    /// Lower bound of bucket `i`
+    pub fn bucket_start(&self, i: usize) -> u32 {
+        self.lo.saturating_add(self.width.saturating_mul(i as u32))
+    }
+}
+
+fn isqrt(n: u64) -> u64 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n / 2;
+    let mut y = (x + n / x) / 2;
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+impl<const B: usize> defmt::Format for Stats<B> {
+    fn format(&self, f: defmt::Formatter) {
+        match (self.min(), self.mean(), self.max()) {
+            (Some(min), Some(mean), Some(max)) => defmt::write!(
+                f,
+                "n={=u32} min={=u32} mean={=u32} max={=u32} sd={=u32} hist={=[?]}",
+                self.count,
+                min,
+                mean,
+                max,
+                self.std_dev().unwrap_or(0),
+                &self.buckets[..]
+            ),
+            _ => defmt::write!(f, "n=0"),
+        }
+    }
+}
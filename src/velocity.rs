@@ -0,0 +1,95 @@
+// Approach velocity and time-to-contact from successive distance readings
+//
+// An alpha-beta tracker: every reading is compared to the distance predicted from the last
+// estimate, and the error nudges both the distance (by `alpha`) and the velocity (by `beta`).
+// Velocity is derived from the actual time between readings, so jittery or missing samples only
+// widen the prediction step instead of skewing the estimate.
+use crate::distance::Distance;
+use fugit::{MicrosDurationU32, TimerDurationU32, TimerInstantU32};
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub struct Estimate {
+    /// Smoothed distance
+    pub distance: Distance,
+    /// Radial velocity in mm/s, negative when approaching
+    pub velocity: i32,
+    /// Time until the object reaches the sensor at the current velocity, in ms
+    pub time_to_contact: Option<u32>,
+}
+
+pub struct Tracker<const FREQ: u32> {
+    alpha: i64,
+    beta: i64,
+    max_gap: TimerDurationU32<FREQ>,
+    // Smoothed distance in um and velocity in um/s
+    x: i64,
+    v: i64,
+    last: Option<TimerInstantU32<FREQ>>,
+    primed: bool,
+}
+
+impl<const FREQ: u32> Tracker<FREQ> {
+    /// `alpha` and `beta` are gains in 1/256ths, readings more than `max_gap` apart restart
+    /// the tracker
+    pub fn new(alpha: u8, beta: u8, max_gap: TimerDurationU32<FREQ>) -> Self {
+        Tracker {
+            alpha: alpha as i64,
+            beta: beta as i64,
+            max_gap,
+            x: 0,
+            v: 0,
+            last: None,
+            primed: false,
+        }
+    }
+
+    /// Forgets the object being tracked
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.primed = false;
+    }
+
+    /// Feeds a reading taken at `now`, returns an estimate once two readings are in
+    pub fn update(&mut self, d: Distance, now: TimerInstantU32<FREQ>) -> Option<Estimate> {
+        let z = d.um() as i64;
+        let dt = match self.last {
+            Some(last) if now - last <= self.max_gap => now - last,
+            _ => {
+                self.x = z;
+                self.v = 0;
+                self.last = Some(now);
+                self.primed = false;
+                return None;
+            }
+        };
+        let dt: MicrosDurationU32 = dt.convert();
+        let dt_us = dt.ticks() as i64;
+        if dt_us == 0 {
+            return None;
+        }
+        self.last = Some(now);
+
+        if self.primed {
+            let predicted = self.x + self.v * dt_us / 1_000_000;
+            let residual = z - predicted;
+            self.x = predicted + self.alpha * residual / 256;
+            self.v += self.beta * residual * 1_000_000 / (256 * dt_us);
+        } else {
+            // Second reading, start out with the raw slope
+            self.v = (z - self.x) * 1_000_000 / dt_us;
+            self.x = z;
+            self.primed = true;
+        }
+
+        let time_to_contact = if self.v < 0 && self.x > 0 {
+            Some((self.x * 1_000 / -self.v) as u32)
+        } else {
+            None
+        };
+        Some(Estimate {
+            distance: Distance::from_um(self.x.max(0) as u32),
+            velocity: (self.v / 1_000) as i32,
+            time_to_contact,
+        })
+    }
+}
@@ -0,0 +1,416 @@
+// Driver for the ST VL53L0X time-of-flight ranger
+//
+// Init follows ST's API as condensed by Pololu's vl53l0x-arduino: load the tuning settings,
+// configure the reference SPADs and run the VHV/phase reference calibrations. The sensor is then
+// used in single-shot mode with the default ~33 ms timing budget. Dropping the MSRC and TCC steps
+// would shorten it, so the final range timeout is stretched to keep the budget the defaults add up
+// to, the way `setMeasurementTimingBudget` does.
+use crate::{distance::Distance, range::RangeSensor};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+pub const DEFAULT_ADDRESS: u8 = 0x29;
+
+/// Readings at or above this mean nothing was in range
+const OUT_OF_RANGE_MM: u16 = 8190;
+// Number of register polls before a busy wait gives up
+const POLL_LIMIT: u32 = 100_000;
+const MODEL_ID: u8 = 0xEE;
+
+const SYSRANGE_START: u8 = 0x00;
+const SYSTEM_SEQUENCE_CONFIG: u8 = 0x01;
+const SYSTEM_INTERRUPT_CONFIG_GPIO: u8 = 0x0A;
+const SYSTEM_INTERRUPT_CLEAR: u8 = 0x0B;
+const RESULT_INTERRUPT_STATUS: u8 = 0x13;
+const RESULT_RANGE_STATUS: u8 = 0x14;
+const FINAL_RANGE_CONFIG_MIN_COUNT_RATE_RTN_LIMIT: u8 = 0x44;
+const MSRC_CONFIG_TIMEOUT_MACROP: u8 = 0x46;
+const DYNAMIC_SPAD_NUM_REQUESTED_REF_SPAD: u8 = 0x4E;
+const DYNAMIC_SPAD_REF_EN_START_OFFSET: u8 = 0x4F;
+const PRE_RANGE_CONFIG_VCSEL_PERIOD: u8 = 0x50;
+const PRE_RANGE_CONFIG_TIMEOUT_MACROP_HI: u8 = 0x51;
+const MSRC_CONFIG_CONTROL: u8 = 0x60;
+const FINAL_RANGE_CONFIG_VCSEL_PERIOD: u8 = 0x70;
+const FINAL_RANGE_CONFIG_TIMEOUT_MACROP_HI: u8 = 0x71;
+const GPIO_HV_MUX_ACTIVE_HIGH: u8 = 0x84;
+const VHV_CONFIG_PAD_SCL_SDA_EXTSUP_HV: u8 = 0x89;
+const GLOBAL_CONFIG_SPAD_ENABLES_REF_0: u8 = 0xB0;
+const GLOBAL_CONFIG_REF_EN_START_SELECT: u8 = 0xB6;
+const IDENTIFICATION_MODEL_ID: u8 = 0xC0;
+
+// SYSTEM_SEQUENCE_CONFIG bits
+const STEP_MSRC: u8 = 0x04;
+const STEP_DSS: u8 = 0x08;
+const STEP_TCC: u8 = 0x10;
+const STEP_PRE_RANGE: u8 = 0x40;
+const STEP_FINAL_RANGE: u8 = 0x80;
+
+// Timing budget taken by each sequence step besides its timeout, in us
+const START_OVERHEAD_US: u32 = 1910;
+const END_OVERHEAD_US: u32 = 960;
+const MSRC_OVERHEAD_US: u32 = 660;
+const TCC_OVERHEAD_US: u32 = 590;
+const DSS_OVERHEAD_US: u32 = 690;
+const PRE_RANGE_OVERHEAD_US: u32 = 660;
+const FINAL_RANGE_OVERHEAD_US: u32 = 550;
+
+// Register/value pairs from ST's DefaultTuningSettings
+#[rustfmt::skip]
+const TUNING_SETTINGS: [(u8, u8); 80] = [
+    (0xFF, 0x01), (0x00, 0x00), (0xFF, 0x00), (0x09, 0x00), (0x10, 0x00), (0x11, 0x00),
+    (0x24, 0x01), (0x25, 0xFF), (0x75, 0x00), (0xFF, 0x01), (0x4E, 0x2C), (0x48, 0x00),
+    (0x30, 0x20), (0xFF, 0x00), (0x30, 0x09), (0x54, 0x00), (0x31, 0x04), (0x32, 0x03),
+    (0x40, 0x83), (0x46, 0x25), (0x60, 0x00), (0x27, 0x00), (0x50, 0x06), (0x51, 0x00),
+    (0x52, 0x96), (0x56, 0x08), (0x57, 0x30), (0x61, 0x00), (0x62, 0x00), (0x64, 0x00),
+    (0x65, 0x00), (0x66, 0xA0), (0xFF, 0x01), (0x22, 0x32), (0x47, 0x14), (0x49, 0xFF),
+    (0x4A, 0x00), (0xFF, 0x00), (0x7A, 0x0A), (0x7B, 0x00), (0x78, 0x21), (0xFF, 0x01),
+    (0x23, 0x34), (0x42, 0x00), (0x44, 0xFF), (0x45, 0x26), (0x46, 0x05), (0x40, 0x40),
+    (0x0E, 0x06), (0x20, 0x1A), (0x43, 0x40), (0xFF, 0x00), (0x34, 0x03), (0x35, 0x44),
+    (0xFF, 0x01), (0x31, 0x04), (0x4B, 0x09), (0x4C, 0x05), (0x4D, 0x04), (0xFF, 0x00),
+    (0x44, 0x00), (0x45, 0x20), (0x47, 0x08), (0x48, 0x28), (0x67, 0x00), (0x70, 0x04),
+    (0x71, 0x01), (0x72, 0xFE), (0x76, 0x00), (0x77, 0x00), (0xFF, 0x01), (0x0D, 0x01),
+    (0xFF, 0x00), (0x80, 0x01), (0x01, 0xF8), (0xFF, 0x01), (0x8E, 0x01), (0x00, 0x01),
+    (0xFF, 0x00), (0x80, 0x00),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Error<E> {
+    /// I2C bus error
+    I2c(E),
+    /// Something other than the expected sensor answered at the address
+    WrongDevice,
+    /// The sensor didn't finish an internal operation in time
+    Timeout,
+    /// Nothing was in range
+    OutOfRange,
+}
+
+impl<E> From<E> for Error<E> {
+    fn from(e: E) -> Self {
+        Error::I2c(e)
+    }
+}
+
+impl<E> defmt::Format for Error<E> {
+    fn format(&self, f: defmt::Formatter) {
+        match self {
+            Error::I2c(_) => defmt::write!(f, "I2C error"),
+            Error::WrongDevice => defmt::write!(f, "WrongDevice"),
+            Error::Timeout => defmt::write!(f, "Timeout"),
+            Error::OutOfRange => defmt::write!(f, "OutOfRange"),
+        }
+    }
+}
+
+// Timeouts of the sequence steps, as the sensor has them
+struct Timeouts {
+    steps: u8,
+    msrc_dss_tcc_us: u32,
+    pre_range_mclks: u32,
+    pre_range_us: u32,
+    final_range_vcsel_pclks: u32,
+    final_range_us: u32,
+}
+
+impl Timeouts {
+    // Budget taken by all but the final range step
+    fn used_us(&self) -> u32 {
+        let mut used = START_OVERHEAD_US + END_OVERHEAD_US;
+        if self.steps & STEP_TCC != 0 {
+            used += self.msrc_dss_tcc_us + TCC_OVERHEAD_US;
+        }
+        if self.steps & STEP_DSS != 0 {
+            used += 2 * (self.msrc_dss_tcc_us + DSS_OVERHEAD_US);
+        } else if self.steps & STEP_MSRC != 0 {
+            used += self.msrc_dss_tcc_us + MSRC_OVERHEAD_US;
+        }
+        if self.steps & STEP_PRE_RANGE != 0 {
+            used += self.pre_range_us + PRE_RANGE_OVERHEAD_US;
+        }
+        used
+    }
+}
+
+pub struct Vl53l0x<I2C> {
+    i2c: I2C,
+    address: u8,
+    stop_variable: u8,
+}
+
+impl<I2C, E> Vl53l0x<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Checks the model id and initializes the sensor
+    pub fn new(i2c: I2C, address: u8) -> Result<Self, Error<E>> {
+        let mut sensor = Vl53l0x {
+            i2c,
+            address,
+            stop_variable: 0,
+        };
+        if sensor.read_reg(IDENTIFICATION_MODEL_ID)? != MODEL_ID {
+            return Err(Error::WrongDevice);
+        }
+        sensor.init()?;
+        Ok(sensor)
+    }
+
+    pub fn free(self) -> I2C {
+        self.i2c
+    }
+
+    fn init(&mut self) -> Result<(), Error<E>> {
+        // 2V8 I/O mode
+        let hv = self.read_reg(VHV_CONFIG_PAD_SCL_SDA_EXTSUP_HV)?;
+        self.write_reg(VHV_CONFIG_PAD_SCL_SDA_EXTSUP_HV, hv | 0x01)?;
+
+        // I2C standard mode
+        self.write_reg(0x88, 0x00)?;
+        self.write_regs(&[(0x80, 0x01), (0xFF, 0x01), (0x00, 0x00)])?;
+        self.stop_variable = self.read_reg(0x91)?;
+        self.write_regs(&[(0x00, 0x01), (0xFF, 0x00), (0x80, 0x00)])?;
+
+        // Disable the MSRC and PRE_RANGE signal rate limit checks
+        let msrc = self.read_reg(MSRC_CONFIG_CONTROL)?;
+        self.write_reg(MSRC_CONFIG_CONTROL, msrc | 0x12)?;
+        // Final range signal rate limit of 0.25 MCPS (Q9.7)
+        self.write_reg16(FINAL_RANGE_CONFIG_MIN_COUNT_RATE_RTN_LIMIT, 32)?;
+        self.write_reg(SYSTEM_SEQUENCE_CONFIG, 0xFF)?;
+
+        self.init_ref_spads()?;
+        self.write_regs(&TUNING_SETTINGS)?;
+
+        // Interrupt on new sample ready, active low
+        self.write_reg(SYSTEM_INTERRUPT_CONFIG_GPIO, 0x04)?;
+        let mux = self.read_reg(GPIO_HV_MUX_ACTIVE_HIGH)?;
+        self.write_reg(GPIO_HV_MUX_ACTIVE_HIGH, mux & !0x10)?;
+        self.write_reg(SYSTEM_INTERRUPT_CLEAR, 0x01)?;
+
+        // Skip the MSRC and TCC steps, keeping the budget all steps add up to
+        let budget_us = self.timing_budget_us()?;
+        self.write_reg(SYSTEM_SEQUENCE_CONFIG, 0xE8)?;
+
+        // VHV and phase reference calibration
+        self.write_reg(SYSTEM_SEQUENCE_CONFIG, 0x01)?;
+        self.calibrate(0x40)?;
+        self.write_reg(SYSTEM_SEQUENCE_CONFIG, 0x02)?;
+        self.calibrate(0x00)?;
+        self.write_reg(SYSTEM_SEQUENCE_CONFIG, 0xE8)?;
+        self.set_timing_budget_us(budget_us)
+    }
+
+    fn timeouts(&mut self) -> Result<Timeouts, Error<E>> {
+        let steps = self.read_reg(SYSTEM_SEQUENCE_CONFIG)?;
+        let pre_range_vcsel_pclks = vcsel_pclks(self.read_reg(PRE_RANGE_CONFIG_VCSEL_PERIOD)?);
+        let msrc_mclks = self.read_reg(MSRC_CONFIG_TIMEOUT_MACROP)? as u32 + 1;
+        let pre_range_mclks = decode_timeout(self.read_reg16(PRE_RANGE_CONFIG_TIMEOUT_MACROP_HI)?);
+        let final_range_vcsel_pclks = vcsel_pclks(self.read_reg(FINAL_RANGE_CONFIG_VCSEL_PERIOD)?);
+        let mut final_range_mclks =
+            decode_timeout(self.read_reg16(FINAL_RANGE_CONFIG_TIMEOUT_MACROP_HI)?);
+        // The final range timeout includes the pre-range one
+        if steps & STEP_PRE_RANGE != 0 {
+            final_range_mclks = final_range_mclks.saturating_sub(pre_range_mclks);
+        }
+        Ok(Timeouts {
+            steps,
+            msrc_dss_tcc_us: mclks_to_us(msrc_mclks, pre_range_vcsel_pclks),
+            pre_range_mclks,
+            pre_range_us: mclks_to_us(pre_range_mclks, pre_range_vcsel_pclks),
+            final_range_vcsel_pclks,
+            final_range_us: mclks_to_us(final_range_mclks, final_range_vcsel_pclks),
+        })
+    }
+
+    // Time a measurement takes with the steps enabled now
+    fn timing_budget_us(&mut self) -> Result<u32, Error<E>> {
+        let timeouts = self.timeouts()?;
+        let mut budget = timeouts.used_us();
+        if timeouts.steps & STEP_FINAL_RANGE != 0 {
+            budget += timeouts.final_range_us + FINAL_RANGE_OVERHEAD_US;
+        }
+        Ok(budget)
+    }
+
+    // Gives the final range step whatever is left of `budget_us` after the other steps
+    fn set_timing_budget_us(&mut self, budget_us: u32) -> Result<(), Error<E>> {
+        let timeouts = self.timeouts()?;
+        if timeouts.steps & STEP_FINAL_RANGE == 0 {
+            return Ok(());
+        }
+        let left = budget_us.saturating_sub(timeouts.used_us() + FINAL_RANGE_OVERHEAD_US);
+        let mut mclks = us_to_mclks(left, timeouts.final_range_vcsel_pclks);
+        if timeouts.steps & STEP_PRE_RANGE != 0 {
+            mclks += timeouts.pre_range_mclks;
+        }
+        self.write_reg16(FINAL_RANGE_CONFIG_TIMEOUT_MACROP_HI, encode_timeout(mclks))
+    }
+
+    // Enables the reference SPADs reported in the sensor's NVM
+    fn init_ref_spads(&mut self) -> Result<(), Error<E>> {
+        self.write_regs(&[(0x80, 0x01), (0xFF, 0x01), (0x00, 0x00), (0xFF, 0x06)])?;
+        let r = self.read_reg(0x83)?;
+        self.write_reg(0x83, r | 0x04)?;
+        self.write_regs(&[
+            (0xFF, 0x07),
+            (0x81, 0x01),
+            (0x80, 0x01),
+            (0x94, 0x6B),
+            (0x83, 0x00),
+        ])?;
+        self.poll(|s| Ok(s.read_reg(0x83)? != 0x00))?;
+        self.write_reg(0x83, 0x01)?;
+        let info = self.read_reg(0x92)?;
+        let count = info & 0x7F;
+        let is_aperture = info & 0x80 != 0;
+        self.write_regs(&[(0x81, 0x00), (0xFF, 0x06)])?;
+        let r = self.read_reg(0x83)?;
+        self.write_reg(0x83, r & !0x04)?;
+        self.write_regs(&[(0xFF, 0x01), (0x00, 0x01), (0xFF, 0x00), (0x80, 0x00)])?;
+
+        let mut map = [0; 6];
+        self.i2c
+            .write_read(self.address, &[GLOBAL_CONFIG_SPAD_ENABLES_REF_0], &mut map)?;
+        self.write_regs(&[
+            (0xFF, 0x01),
+            (DYNAMIC_SPAD_REF_EN_START_OFFSET, 0x00),
+            (DYNAMIC_SPAD_NUM_REQUESTED_REF_SPAD, 0x2C),
+            (0xFF, 0x00),
+            (GLOBAL_CONFIG_REF_EN_START_SELECT, 0xB4),
+        ])?;
+        // Aperture SPADs start at 12
+        let first = if is_aperture { 12 } else { 0 };
+        let mut enabled = 0;
+        for i in 0..48 {
+            let bit = 1 << (i % 8);
+            if i < first || enabled == count {
+                map[i / 8] &= !bit;
+            } else if map[i / 8] & bit != 0 {
+                enabled += 1;
+            }
+        }
+        let mut buf = [0; 7];
+        buf[0] = GLOBAL_CONFIG_SPAD_ENABLES_REF_0;
+        buf[1..].copy_from_slice(&map);
+        self.i2c.write(self.address, &buf)?;
+        Ok(())
+    }
+
+    fn calibrate(&mut self, vhv_init: u8) -> Result<(), Error<E>> {
+        self.write_reg(SYSRANGE_START, 0x01 | vhv_init)?;
+        self.poll(|s| s.is_ready())?;
+        self.write_reg(SYSTEM_INTERRUPT_CLEAR, 0x01)?;
+        self.write_reg(SYSRANGE_START, 0x00)
+    }
+
+    fn is_ready(&mut self) -> Result<bool, Error<E>> {
+        Ok(self.read_reg(RESULT_INTERRUPT_STATUS)? & 0x07 != 0)
+    }
+
+    fn poll(
+        &mut self,
+        mut done: impl FnMut(&mut Self) -> Result<bool, Error<E>>,
+    ) -> Result<(), Error<E>> {
+        for _ in 0..POLL_LIMIT {
+            if done(self)? {
+                return Ok(());
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    fn read_reg(&mut self, reg: u8) -> Result<u8, E> {
+        let mut buf = [0];
+        self.i2c.write_read(self.address, &[reg], &mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_reg16(&mut self, reg: u8) -> Result<u16, E> {
+        let mut buf = [0; 2];
+        self.i2c.write_read(self.address, &[reg], &mut buf)?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    fn write_reg(&mut self, reg: u8, value: u8) -> Result<(), Error<E>> {
+        Ok(self.i2c.write(self.address, &[reg, value])?)
+    }
+
+    fn write_reg16(&mut self, reg: u8, value: u16) -> Result<(), Error<E>> {
+        let [hi, lo] = value.to_be_bytes();
+        Ok(self.i2c.write(self.address, &[reg, hi, lo])?)
+    }
+
+    fn write_regs(&mut self, regs: &[(u8, u8)]) -> Result<(), Error<E>> {
+        regs.iter()
+            .try_for_each(|&(reg, value)| self.write_reg(reg, value))
+    }
+}
+
+// VCSEL period register value to PCLKs
+fn vcsel_pclks(reg: u8) -> u32 {
+    (reg as u32 + 1) << 1
+}
+
+// Macro period in ns, 2304 VCSEL periods of 1655 ps
+fn macro_period_ns(vcsel_pclks: u32) -> u64 {
+    (2304 * vcsel_pclks as u64 * 1655 + 500) / 1000
+}
+
+fn mclks_to_us(mclks: u32, vcsel_pclks: u32) -> u32 {
+    ((mclks as u64 * macro_period_ns(vcsel_pclks) + 500) / 1000) as u32
+}
+
+fn us_to_mclks(us: u32, vcsel_pclks: u32) -> u32 {
+    let period = macro_period_ns(vcsel_pclks);
+    ((us as u64 * 1000 + period / 2) / period) as u32
+}
+
+// Timeouts are stored as (LSB + 1) << MSB MCLKs
+fn decode_timeout(reg: u16) -> u32 {
+    ((reg & 0xFF) as u32).wrapping_shl((reg >> 8) as u32) + 1
+}
+
+fn encode_timeout(mclks: u32) -> u16 {
+    if mclks == 0 {
+        return 0;
+    }
+    let (mut lsb, mut msb) = (mclks - 1, 0);
+    while lsb > 0xFF {
+        lsb >>= 1;
+        msb += 1;
+    }
+    (msb << 8 | lsb) as u16
+}
+
+impl<I2C, E> RangeSensor for Vl53l0x<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Error = Error<E>;
+
+    fn start(&mut self) -> Result<(), Error<E>> {
+        self.write_regs(&[
+            (0x80, 0x01),
+            (0xFF, 0x01),
+            (0x00, 0x00),
+            (0x91, self.stop_variable),
+            (0x00, 0x01),
+            (0xFF, 0x00),
+            (0x80, 0x00),
+            (SYSRANGE_START, 0x01),
+        ])
+    }
+
+    fn read(&mut self) -> nb::Result<Distance, Error<E>> {
+        if !self.is_ready()? {
+            return Err(nb::Error::WouldBlock);
+        }
+        let mm = self
+            .read_reg16(RESULT_RANGE_STATUS + 10)
+            .map_err(Error::I2c)?;
+        self.write_reg(SYSTEM_INTERRUPT_CLEAR, 0x01)?;
+        if mm >= OUT_OF_RANGE_MM {
+            Err(nb::Error::Other(Error::OutOfRange))
+        } else {
+            Ok(Distance::from_mm(mm as u32))
+        }
+    }
+}
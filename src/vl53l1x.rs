@@ -0,0 +1,161 @@
+// Driver for the ST VL53L1X time-of-flight ranger
+//
+// Init follows ST's ultra lite driver (ULD): load the default configuration block, run one
+// measurement to settle the VHV calibration, then keep the sensor idle. Each `start` runs a
+// single-shot measurement with the ULD's default timing.
+use crate::{distance::Distance, range::RangeSensor};
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+pub use crate::vl53l0x::{Error, DEFAULT_ADDRESS};
+
+// Number of register polls before a busy wait gives up
+const POLL_LIMIT: u32 = 100_000;
+const MODEL_ID: u16 = 0xEACC;
+
+const VHV_CONFIG_TIMEOUT_MACROP_LOOP_BOUND: u16 = 0x0008;
+const VHV_CONFIG_INIT: u16 = 0x000B;
+const DEFAULT_CONFIG_START: u16 = 0x002D;
+const GPIO_HV_MUX_CTRL: u16 = 0x0030;
+const GPIO_TIO_HV_STATUS: u16 = 0x0031;
+const SYSTEM_INTERRUPT_CLEAR: u16 = 0x0086;
+const SYSTEM_MODE_START: u16 = 0x0087;
+const RESULT_RANGE_STATUS: u16 = 0x0089;
+const RESULT_FINAL_CROSSTALK_CORRECTED_RANGE_MM_SD0: u16 = 0x0096;
+const FIRMWARE_SYSTEM_STATUS: u16 = 0x00E5;
+const IDENTIFICATION_MODEL_ID: u16 = 0x010F;
+
+const MODE_STOP: u8 = 0x00;
+const MODE_SINGLE_SHOT: u8 = 0x10;
+const MODE_BACK_TO_BACK: u8 = 0x40;
+
+// ULD's VL51L1X_DEFAULT_CONFIGURATION for registers 0x2D..=0x87
+#[rustfmt::skip]
+const DEFAULT_CONFIG: [u8; 91] = [
+    0x00, 0x00, 0x00, 0x01, 0x02, 0x00, 0x02, 0x08, 0x00, 0x08, 0x10, 0x01, 0x01, 0x00,
+    0x00, 0x00, 0x00, 0xFF, 0x00, 0x0F, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20, 0x0B, 0x00,
+    0x00, 0x02, 0x0A, 0x21, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0xC8, 0x00, 0x00,
+    0x38, 0xFF, 0x01, 0x00, 0x08, 0x00, 0x00, 0x01, 0xCC, 0x0F, 0x01, 0xF1, 0x0D, 0x01,
+    0x68, 0x00, 0x80, 0x08, 0xB8, 0x00, 0x00, 0x00, 0x00, 0x0F, 0x89, 0x00, 0x00, 0x00,
+    0x00, 0x00, 0x00, 0x00, 0x01, 0x0F, 0x0D, 0x0E, 0x0E, 0x00, 0x00, 0x02, 0xC7, 0xFF,
+    0x9B, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00,
+];
+
+// Device range status to ULD status, 0 is a valid measurement
+#[rustfmt::skip]
+const RANGE_STATUS: [u8; 24] = [
+    255, 255, 255, 5, 2, 4, 1, 7, 3, 0, 255, 255, 9, 13, 255, 255, 255, 255, 10, 6, 255, 255,
+    11, 12,
+];
+
+pub struct Vl53l1x<I2C> {
+    i2c: I2C,
+    address: u8,
+    // Level of GPIO__TIO_HV_STATUS that signals new data
+    ready_level: u8,
+}
+
+impl<I2C, E> Vl53l1x<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    /// Waits for the sensor to boot, checks the model id and initializes it
+    pub fn new(i2c: I2C, address: u8) -> Result<Self, Error<E>> {
+        let mut sensor = Vl53l1x {
+            i2c,
+            address,
+            ready_level: 1,
+        };
+        sensor.poll(|s| Ok(s.read_reg(FIRMWARE_SYSTEM_STATUS)? & 0x01 != 0))?;
+        if sensor.read_reg16(IDENTIFICATION_MODEL_ID)? != MODEL_ID {
+            return Err(Error::WrongDevice);
+        }
+        sensor.init()?;
+        Ok(sensor)
+    }
+
+    pub fn free(self) -> I2C {
+        self.i2c
+    }
+
+    fn init(&mut self) -> Result<(), Error<E>> {
+        let mut buf = [0; 93];
+        buf[..2].copy_from_slice(&DEFAULT_CONFIG_START.to_be_bytes());
+        buf[2..].copy_from_slice(&DEFAULT_CONFIG);
+        self.i2c.write(self.address, &buf)?;
+
+        let polarity = (self.read_reg(GPIO_HV_MUX_CTRL)? & 0x10) >> 4;
+        self.ready_level = (polarity == 0) as u8;
+
+        // A first measurement runs the VHV calibration
+        self.write_reg(SYSTEM_MODE_START, MODE_BACK_TO_BACK)?;
+        self.poll(|s| s.is_ready())?;
+        self.write_reg(SYSTEM_INTERRUPT_CLEAR, 0x01)?;
+        self.write_reg(SYSTEM_MODE_START, MODE_STOP)?;
+
+        // Two bounds VHV, start VHV from the previous temperature
+        self.write_reg(VHV_CONFIG_TIMEOUT_MACROP_LOOP_BOUND, 0x09)?;
+        self.write_reg(VHV_CONFIG_INIT, 0x00)
+    }
+
+    fn is_ready(&mut self) -> Result<bool, Error<E>> {
+        Ok(self.read_reg(GPIO_TIO_HV_STATUS)? & 0x01 == self.ready_level)
+    }
+
+    fn poll(
+        &mut self,
+        mut done: impl FnMut(&mut Self) -> Result<bool, Error<E>>,
+    ) -> Result<(), Error<E>> {
+        for _ in 0..POLL_LIMIT {
+            if done(self)? {
+                return Ok(());
+            }
+        }
+        Err(Error::Timeout)
+    }
+
+    fn read_reg(&mut self, reg: u16) -> Result<u8, E> {
+        let mut buf = [0];
+        self.i2c
+            .write_read(self.address, &reg.to_be_bytes(), &mut buf)?;
+        Ok(buf[0])
+    }
+
+    fn read_reg16(&mut self, reg: u16) -> Result<u16, E> {
+        let mut buf = [0; 2];
+        self.i2c
+            .write_read(self.address, &reg.to_be_bytes(), &mut buf)?;
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    fn write_reg(&mut self, reg: u16, value: u8) -> Result<(), Error<E>> {
+        let [hi, lo] = reg.to_be_bytes();
+        Ok(self.i2c.write(self.address, &[hi, lo, value])?)
+    }
+}
+
+impl<I2C, E> RangeSensor for Vl53l1x<I2C>
+where
+    I2C: Write<Error = E> + WriteRead<Error = E>,
+{
+    type Error = Error<E>;
+
+    fn start(&mut self) -> Result<(), Error<E>> {
+        self.write_reg(SYSTEM_INTERRUPT_CLEAR, 0x01)?;
+        self.write_reg(SYSTEM_MODE_START, MODE_SINGLE_SHOT)
+    }
+
+    fn read(&mut self) -> nb::Result<Distance, Error<E>> {
+        if !self.is_ready()? {
+            return Err(nb::Error::WouldBlock);
+        }
+        let status = self.read_reg(RESULT_RANGE_STATUS).map_err(Error::I2c)? & 0x1F;
+        let mm = self
+            .read_reg16(RESULT_FINAL_CROSSTALK_CORRECTED_RANGE_MM_SD0)
+            .map_err(Error::I2c)?;
+        self.write_reg(SYSTEM_INTERRUPT_CLEAR, 0x01)?;
+        match RANGE_STATUS.get(status as usize) {
+            Some(0) => Ok(Distance::from_mm(mm as u32)),
+            _ => Err(nb::Error::Other(Error::OutOfRange)),
+        }
+    }
+}
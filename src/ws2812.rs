@@ -0,0 +1,134 @@
+// WS2812 and SK6812 RGB LED strip driver on a PWM peripheral
+//
+// Every data bit becomes one 1.25 us PWM period at 16 MHz, high for 0.375 us for a zero and
+// 0.8125 us for a one, in a buffer the PWM plays through EasyDMA. The buffer ends in low periods
+// for the latch, long enough for the SK6812 too. The sequence end stops the PWM through a short,
+// so a transfer runs without interrupts and `write` only has to wait when the last one is still
+// going, at most 30 us per LED.
+//
+// Frames go out with the brightness applied, gamma corrected and, with a current limit set,
+// dimmed as a whole to stay below it.
+use crate::rgb::{current_ma, Rgb};
+use nrf52840_hal::{
+    gpio::{Output, Pin, PushPull},
+    pwm::Instance,
+};
+
+/// Low periods at the end, 100 us
+pub const RESET_PERIODS: usize = 80;
+
+// Ticks at 16 MHz
+const TOP: u16 = 20;
+const T0H: u16 = 6;
+const T1H: u16 = 13;
+// The period starts high and falls at the compare value
+const FALLING: u16 = 0x8000;
+
+/// Buffer length for a strip of `leds`
+pub const fn buffer_len(leds: usize) -> usize {
+    leds * 24 + RESET_PERIODS
+}
+
+pub struct Ws2812<T: Instance> {
+    pwm: T,
+    _pin: Pin<Output<PushPull>>,
+    buffer: &'static mut [u16],
+    brightness: u8,
+    limit_ma: Option<u32>,
+}
+
+impl<T: Instance> Ws2812<T> {
+    /// `pin` drives the strip's data input and should be set low, `buffer` sets the strip
+    /// length, see `buffer_len`
+    pub fn new(pwm: T, pin: Pin<Output<PushPull>>, buffer: &'static mut [u16]) -> Self {
+        assert!(buffer.len() >= RESET_PERIODS, "buffer too short");
+        pwm.psel.out[0].write(|w| unsafe { w.bits(pin.psel_bits()) });
+        pwm.mode.write(|w| w.updown().up());
+        pwm.prescaler.write(|w| w.prescaler().div_1());
+        pwm.countertop
+            .write(|w| unsafe { w.countertop().bits(TOP) });
+        pwm.decoder
+            .write(|w| w.load().common().mode().refresh_count());
+        pwm.loop_.write(|w| w.cnt().disabled());
+        pwm.seq0.refresh.write(|w| unsafe { w.bits(0) });
+        pwm.seq0.enddelay.write(|w| unsafe { w.bits(0) });
+        pwm.shorts.write(|w| w.seqend0_stop().enabled());
+        pwm.enable.write(|w| w.enable().enabled());
+        // Nothing to wait for before the first write
+        pwm.events_stopped.write(|w| unsafe { w.bits(1) });
+        Ws2812 {
+            pwm,
+            _pin: pin,
+            buffer,
+            brightness: 255,
+            limit_ma: None,
+        }
+    }
+
+    /// LEDs the buffer has room for
+    pub fn len(&self) -> usize {
+        (self.buffer.len() - RESET_PERIODS) / 24
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Scales all frames by `brightness` / 255, before gamma correction
+    pub fn set_brightness(&mut self, brightness: u8) {
+        self.brightness = brightness;
+    }
+
+    /// Dims frames that would draw more than `ma`
+    pub fn set_current_limit(&mut self, ma: Option<u32>) {
+        self.limit_ma = ma;
+    }
+
+    /// A transfer is still going
+    pub fn is_busy(&self) -> bool {
+        self.pwm.events_stopped.read().bits() == 0
+    }
+
+    /// Sends `frame` to the first `frame.len()` LEDs
+    ///
+    /// Waits for the previous transfer first, and returns once this one is started.
+    pub fn write(&mut self, frame: &[Rgb]) {
+        let n = frame.len().min(self.len());
+        while self.is_busy() {}
+
+        let brightness = self.brightness;
+        let corrected = frame[..n].iter().map(|c| c.scale(brightness).gamma());
+        let limit = self.limit_ma.map_or(255, |limit| {
+            let ma = current_ma(corrected.clone());
+            if ma > limit {
+                (limit * 255 / ma) as u8
+            } else {
+                255
+            }
+        });
+
+        let bits = self.buffer.chunks_exact_mut(24);
+        for (c, bits) in corrected.zip(bits) {
+            let c = c.scale(limit);
+            // Green first, most significant bit first
+            let word = (c.g as u32) << 16 | (c.r as u32) << 8 | c.b as u32;
+            for (i, bit) in bits.iter_mut().enumerate() {
+                let one = word & 1 << (23 - i) != 0;
+                *bit = FALLING | if one { T1H } else { T0H };
+            }
+        }
+        let len = buffer_len(n);
+        for value in self.buffer[n * 24..len].iter_mut() {
+            *value = FALLING;
+        }
+
+        self.pwm
+            .seq0
+            .ptr
+            .write(|w| unsafe { w.bits(self.buffer.as_ptr() as u32) });
+        self.pwm.seq0.cnt.write(|w| unsafe { w.bits(len as u32) });
+        self.pwm.events_stopped.write(|w| w);
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+        self.pwm.tasks_seqstart[0].write(|w| unsafe { w.bits(1) });
+    }
+}
@@ -0,0 +1,79 @@
+// Proximity zones with hysteresis
+//
+// `N` boundaries split the range into `N + 1` zones, zone 0 being the closest one. A boundary
+// only counts as crossed once the distance is `hysteresis` past it, and the new zone must be held
+// for the dwell time before a `ZoneChanged` event is emitted. This keeps a reading hovering
+// around a boundary from flickering between zones.
+use crate::distance::Distance;
+use fugit::{TimerDurationU32, TimerInstantU32};
+
+#[derive(Clone, Copy, Debug, PartialEq, defmt::Format)]
+pub struct ZoneChanged {
+    pub from: usize,
+    pub to: usize,
+}
+
+pub struct Zones<const N: usize, const FREQ: u32> {
+    bounds: [Distance; N],
+    hysteresis: Distance,
+    dwell: TimerDurationU32<FREQ>,
+    zone: usize,
+    candidate: Option<(usize, TimerInstantU32<FREQ>)>,
+}
+
+impl<const N: usize, const FREQ: u32> Zones<N, FREQ> {
+    /// `bounds` must be sorted nearest first and more than `2 * hysteresis` apart
+    pub fn new(bounds: [Distance; N], hysteresis: Distance, dwell: TimerDurationU32<FREQ>) -> Self {
+        assert!(bounds
+            .windows(2)
+            .all(|w| w[1].saturating_sub(w[0]) > hysteresis * 2));
+        Zones {
+            bounds,
+            hysteresis,
+            dwell,
+            // Start out with nothing in range
+            zone: N,
+            candidate: None,
+        }
+    }
+
+    /// The zone the last change settled on
+    pub fn zone(&self) -> usize {
+        self.zone
+    }
+
+    /// Zone `d` belongs to, seen from the current zone
+    fn classify(&self, d: Distance) -> usize {
+        let mut zone = self.zone;
+        while zone < N && d >= self.bounds[zone] + self.hysteresis {
+            zone += 1;
+        }
+        while zone > 0 && d < self.bounds[zone - 1].saturating_sub(self.hysteresis) {
+            zone -= 1;
+        }
+        zone
+    }
+
+    /// Feeds a new reading taken at `now`
+    pub fn update(&mut self, d: Distance, now: TimerInstantU32<FREQ>) -> Option<ZoneChanged> {
+        let zone = self.classify(d);
+        if zone == self.zone {
+            self.candidate = None;
+            return None;
+        }
+        let since = match self.candidate {
+            Some((candidate, since)) if candidate == zone => since,
+            _ => {
+                self.candidate = Some((zone, now));
+                now
+            }
+        };
+        if now - since < self.dwell {
+            return None;
+        }
+        let from = self.zone;
+        self.zone = zone;
+        self.candidate = None;
+        Some(ZoneChanged { from, to: zone })
+    }
+}
@@ -18,4 +18,637 @@ mod tests {
     fn assert_eq() {
         assert_eq!(24, 42, "TODO: write actual tests")
     }
+
+    #[test]
+    fn median_drops_spikes() {
+        use nrf_play::filter::{Filter, Median};
+        let mut f = Median::<3>::new();
+        assert_eq!(f.feed(100), Some(100));
+        assert_eq!(f.feed(110), Some(105));
+        assert_eq!(f.feed(9000), Some(110));
+        assert_eq!(f.feed(105), Some(110));
+        assert_eq!(f.feed(104), Some(105));
+    }
+
+    #[test]
+    fn ema_converges() {
+        use nrf_play::filter::{Ema, Filter};
+        let mut f = Ema::<2>::new();
+        assert_eq!(f.feed(1000), Some(1000));
+        assert_eq!(f.feed(2000), Some(1250));
+        for _ in 0..64 {
+            f.feed(2000);
+        }
+        assert_eq!(f.feed(2000), Some(2000));
+    }
+
+    #[test]
+    fn outlier_reject_relocks() {
+        use nrf_play::filter::{Filter, OutlierReject};
+        let mut f = OutlierReject::new(50, 2);
+        assert_eq!(f.feed(1000), Some(1000));
+        assert_eq!(f.feed(1040), Some(1040));
+        assert_eq!(f.feed(3000), None);
+        assert_eq!(f.feed(3010), None);
+        assert_eq!(f.feed(3020), Some(3020));
+        assert_eq!(f.feed(3030), Some(3030));
+    }
+
+    #[test]
+    fn chained_filters() {
+        use nrf_play::filter::{Ema, Filter, Median, OutlierReject};
+        let mut f = OutlierReject::new(50, 2)
+            .chain(Median::<3>::new())
+            .chain(Ema::<1>::new());
+        assert_eq!(f.feed(1000), Some(1000));
+        assert_eq!(f.feed(5000), None);
+        assert_eq!(f.feed(1020), Some(1005));
+        f.reset();
+        assert_eq!(f.feed(5000), Some(5000));
+    }
+
+    #[test]
+    fn distance_from_echo() {
+        use nrf_play::{
+            distance::Distance,
+            mono::fugit::{MicrosDurationU32, TimerDurationU32},
+        };
+        let d = Distance::from_echo(MicrosDurationU32::from_ticks(580));
+        assert_eq!(d.um(), 100_000);
+        assert_eq!(d.cm(), 10);
+        assert_eq!(d.mm(), 100);
+        assert_eq!(d.inches(), 4);
+        // 64 MHz ticks keep sub-us resolution
+        let d = Distance::from_echo(TimerDurationU32::<64_000_000>::from_ticks(32));
+        assert_eq!(d.um(), 86);
+    }
+
+    #[test]
+    fn distance_arithmetic() {
+        use nrf_play::distance::Distance;
+        let a = Distance::from_cm(12);
+        let b = Distance::from_mm(45);
+        assert_eq!((a + b).mm(), 165);
+        assert_eq!((a - b).mm(), 75);
+        assert_eq!((b * 2).mm(), 90);
+        assert_eq!((a / 4).mm(), 30);
+        assert_eq!(b.abs_diff(a), a - b);
+        assert_eq!(b.saturating_sub(a), Distance::ZERO);
+        assert!(b < a);
+        assert_eq!(Distance::from_inches(1).mm(), 25);
+    }
+
+    #[test]
+    fn zones_hysteresis_and_dwell() {
+        use nrf_play::{
+            distance::Distance,
+            mono::fugit::{TimerDurationU32, TimerInstantU32},
+            zone::{ZoneChanged, Zones},
+        };
+        let at = TimerInstantU32::<1_000>::from_ticks;
+        let mut zones = Zones::new(
+            [Distance::from_cm(20), Distance::from_cm(50)],
+            Distance::from_cm(2),
+            TimerDurationU32::<1_000>::from_ticks(100),
+        );
+        assert_eq!(zones.zone(), 2);
+
+        // Has to be held for the dwell time
+        assert_eq!(zones.update(Distance::from_cm(30), at(0)), None);
+        assert_eq!(zones.update(Distance::from_cm(30), at(50)), None);
+        assert_eq!(
+            zones.update(Distance::from_cm(30), at(100)),
+            Some(ZoneChanged { from: 2, to: 1 })
+        );
+
+        // Within the hysteresis band nothing changes
+        assert_eq!(zones.update(Distance::from_cm(19), at(200)), None);
+        assert_eq!(zones.update(Distance::from_cm(19), at(400)), None);
+        assert_eq!(zones.zone(), 1);
+
+        // A blip back resets the dwell timer
+        assert_eq!(zones.update(Distance::from_cm(10), at(500)), None);
+        assert_eq!(zones.update(Distance::from_cm(30), at(550)), None);
+        assert_eq!(zones.update(Distance::from_cm(10), at(600)), None);
+        assert_eq!(
+            zones.update(Distance::from_cm(10), at(700)),
+            Some(ZoneChanged { from: 1, to: 0 })
+        );
+    }
+
+    #[test]
+    fn tracker_approach() {
+        use nrf_play::{
+            distance::Distance,
+            mono::fugit::{TimerDurationU32, TimerInstantU32},
+            velocity::Tracker,
+        };
+        let at = TimerInstantU32::<1_000>::from_ticks;
+        let mut tracker = Tracker::new(128, 32, TimerDurationU32::<1_000>::from_ticks(500));
+
+        // Approaching at 200 mm/s from 1 m, with one sample missing
+        assert_eq!(tracker.update(Distance::from_mm(1000), at(0)), None);
+        let e = tracker.update(Distance::from_mm(980), at(100)).unwrap();
+        assert_eq!(e.velocity, -200);
+        assert_eq!(e.time_to_contact, Some(4900));
+        let e = tracker.update(Distance::from_mm(940), at(300)).unwrap();
+        assert_eq!(e.velocity, -200);
+        assert_eq!(e.distance, Distance::from_mm(940));
+
+        // A gap restarts the tracker
+        assert_eq!(tracker.update(Distance::from_mm(500), at(1000)), None);
+        let e = tracker.update(Distance::from_mm(510), at(1100)).unwrap();
+        assert_eq!(e.velocity, 100);
+        assert_eq!(e.time_to_contact, None);
+    }
+
+    #[test]
+    fn stats_summary() {
+        use nrf_play::stats::Stats;
+        let mut stats = Stats::<4>::new(10, 10);
+        assert_eq!(stats.min(), None);
+        assert_eq!(stats.variance(), None);
+
+        for x in [2, 4, 4, 4, 5, 5, 7, 9] {
+            stats.feed(x + 10);
+        }
+        assert_eq!(stats.count(), 8);
+        assert_eq!(stats.min(), Some(12));
+        assert_eq!(stats.max(), Some(19));
+        assert_eq!(stats.mean(), Some(15));
+        // 32 / 7
+        assert_eq!(stats.variance(), Some(4));
+        assert_eq!(stats.std_dev(), Some(2));
+        assert_eq!(stats.histogram(), &[8, 0, 0, 0]);
+
+        // Out of range values land in the outer buckets
+        stats.feed(0);
+        stats.feed(25);
+        stats.feed(1000);
+        assert_eq!(stats.histogram(), &[9, 1, 0, 1]);
+        assert_eq!(stats.bucket_start(3), 40);
+
+        stats.reset();
+        assert_eq!(stats.count(), 0);
+        assert_eq!(stats.histogram(), &[0; 4]);
+    }
+
+    #[test]
+    fn calibration_two_points() {
+        use nrf_play::{calibration::Calibration, distance::Distance};
+        // Sensor reads 2 cm long and 5% short
+        let raw = |cm: u32| Distance::from_mm(cm * 95 / 10 + 20);
+        let cal = Calibration::from_points(
+            [raw(20), raw(100)],
+            [Distance::from_cm(20), Distance::from_cm(100)],
+        )
+        .unwrap();
+        assert_eq!(cal.apply(raw(20)).mm(), 200);
+        assert_eq!(cal.apply(raw(100)).mm(), 1000);
+        assert_eq!(cal.apply(raw(50)).mm(), 500);
+        assert_eq!(Calibration::IDENTITY.apply(raw(50)), raw(50));
+
+        // Same reading twice or a negative slope can't be used
+        assert_eq!(
+            Calibration::from_points(
+                [raw(20), raw(20)],
+                [Distance::from_cm(20), Distance::from_cm(100)]
+            ),
+            None
+        );
+        assert_eq!(
+            Calibration::from_points(
+                [raw(100), raw(20)],
+                [Distance::from_cm(20), Distance::from_cm(100)]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn ring_overwrites_oldest() {
+        use nrf_play::ring::Ring;
+        let mut ring = Ring::<u32, 3>::new();
+        assert_eq!(ring.pop(), None);
+
+        for i in 0..5 {
+            ring.push(i);
+        }
+        assert_eq!(ring.len(), 3);
+        assert_eq!(ring.take_dropped(), 2);
+        assert_eq!(ring.take_dropped(), 0);
+        assert_eq!(ring.pop(), Some(2));
+
+        ring.push(5);
+        assert_eq!(ring.pop(), Some(3));
+        assert_eq!(ring.pop(), Some(4));
+        assert_eq!(ring.pop(), Some(5));
+        assert!(ring.is_empty());
+    }
+
+    #[test]
+    fn button_events() {
+        use nrf_play::{
+            button::{Button, Event, Timings},
+            mono::fugit::TimerInstantU32,
+        };
+        // Samples every 5 ms from `from` up to `to`, times in ms
+        fn run(button: &mut Button<1_000>, pressed: bool, from: u32, to: u32) {
+            for t in (from..to).step_by(5) {
+                button.update(pressed, TimerInstantU32::from_ticks(t));
+            }
+        }
+        let mut button = Button::new(Timings::default());
+
+        // Bounces are ignored, the click waits for the double click window to pass
+        run(&mut button, true, 0, 5);
+        run(&mut button, false, 5, 10);
+        run(&mut button, true, 10, 100);
+        assert_eq!(button.event(), Some(Event::Pressed));
+        assert_eq!(button.event(), None);
+        run(&mut button, false, 100, 400);
+        assert_eq!(button.event(), Some(Event::Released));
+        assert_eq!(button.event(), None);
+        run(&mut button, false, 400, 410);
+        assert_eq!(button.event(), Some(Event::Click));
+        assert!(button.is_idle());
+        // Edges date from the start of the settled level, not from the event
+        assert_eq!(button.pressed_at().ticks(), 10);
+        assert_eq!(button.released_at().ticks(), 100);
+
+        // Two quick clicks
+        run(&mut button, true, 1000, 1080);
+        run(&mut button, false, 1080, 1200);
+        run(&mut button, true, 1200, 1280);
+        run(&mut button, false, 1280, 2000);
+        for event in [
+            Event::Pressed,
+            Event::Released,
+            Event::Pressed,
+            Event::Released,
+            Event::DoubleClick,
+        ] {
+            assert_eq!(button.event(), Some(event));
+        }
+        assert_eq!(button.event(), None);
+
+        // Held: one long press, then repeats after the delay
+        run(&mut button, true, 2000, 3350);
+        run(&mut button, false, 3350, 4000);
+        for event in [
+            Event::Pressed,
+            Event::LongPress(800),
+            Event::Repeat,
+            Event::Repeat,
+            Event::Released,
+        ] {
+            assert_eq!(button.event(), Some(event));
+        }
+        assert_eq!(button.event(), None);
+        assert!(button.is_idle());
+    }
+
+    #[test]
+    fn button_deadline() {
+        use nrf_play::{
+            button::{Button, Event, Timings},
+            mono::fugit::{TimerDurationU32, TimerInstantU32},
+        };
+        let at = TimerInstantU32::<1_000>::from_ticks;
+        let deadline = |button: &Button<1_000>| button.deadline().map(|at| at.ticks());
+        // Levels come in debounced, updates only happen on edges and deadlines
+        let mut button = Button::new(Timings {
+            debounce: TimerDurationU32::from_ticks(0),
+            ..Timings::default()
+        });
+        assert_eq!(deadline(&button), None);
+
+        button.update(true, at(100));
+        assert_eq!(button.event(), Some(Event::Pressed));
+        assert_eq!(deadline(&button), Some(900));
+        button.update(false, at(150));
+        assert_eq!(button.event(), Some(Event::Released));
+        assert_eq!(deadline(&button), Some(451));
+        button.update(false, at(451));
+        assert_eq!(button.event(), Some(Event::Click));
+        assert_eq!(deadline(&button), None);
+
+        button.update(true, at(1000));
+        button.update(true, at(1800));
+        assert_eq!(button.event(), Some(Event::Pressed));
+        assert_eq!(button.event(), Some(Event::LongPress(800)));
+        assert_eq!(deadline(&button), Some(2200));
+        button.update(true, at(2200));
+        assert_eq!(button.event(), Some(Event::Repeat));
+        assert_eq!(deadline(&button), Some(2300));
+        button.update(false, at(2250));
+        assert_eq!(button.event(), Some(Event::Released));
+        assert!(button.is_idle());
+        assert_eq!(deadline(&button), None);
+    }
+
+    #[test]
+    fn button_polling() {
+        use nrf_play::button::Polling;
+        let mut polling = Polling::new();
+        // Only the first edge spawns the task
+        assert!(polling.start());
+        assert!(!polling.start());
+        assert!(polling.keep_going(false));
+        assert!(!polling.start());
+        // Once idle the next edge starts it again
+        assert!(!polling.keep_going(true));
+        assert!(polling.start());
+    }
+
+    #[test]
+    fn key_matrix_ghosting() {
+        use nrf_play::{
+            matrix::{Key, KeyMatrix},
+            mono::fugit::{TimerDurationU32, TimerInstantU32},
+        };
+        let at = TimerInstantU32::<1_000>::from_ticks;
+        let mut matrix = KeyMatrix::<4, 4, 1_000>::new(TimerDurationU32::from_ticks(20));
+        // Row 0 col 1, row 1 col 1 and row 1 col 2
+        let (a, b, c) = (1 << 1, 1 << 5, 1 << 6);
+
+        // A bounce shorter than the debounce time does nothing
+        matrix.update(a, at(0));
+        matrix.update(0, at(5));
+        matrix.update(a, at(10));
+        matrix.update(a, at(25));
+        assert!(matrix.event().is_none());
+        matrix.update(a, at(30));
+        let event = matrix.event().unwrap();
+        assert_eq!(event.key, Key { row: 0, col: 1 });
+        assert!(event.pressed);
+        assert_eq!(event.at.ticks(), 10);
+
+        matrix.update(a | b | c, at(40));
+        matrix.update(a | b | c, at(60));
+        assert_eq!(matrix.pressed(), a | b | c);
+        assert!(matrix.event().unwrap().pressed);
+        assert!(matrix.event().unwrap().pressed);
+
+        // The fourth corner shows up, nothing can be trusted until it's gone
+        let ghost = 1 << 2;
+        matrix.update(a | b | c | ghost, at(70));
+        matrix.update(a | b | c | ghost, at(100));
+        assert!(matrix.is_ghosting());
+        assert!(matrix.event().is_none());
+        assert_eq!(matrix.pressed(), a | b | c);
+
+        matrix.update(0, at(110));
+        assert!(!matrix.is_ghosting());
+        matrix.update(0, at(130));
+        assert_eq!(matrix.pressed(), 0);
+        for _ in 0..3 {
+            assert!(!matrix.event().unwrap().pressed);
+        }
+        assert!(matrix.is_idle());
+    }
+
+    #[test]
+    fn combo_chord_and_sequence() {
+        use nrf_play::{
+            button::Event,
+            combo::{Command, Matcher, Pattern},
+            mono::fugit::{TimerDurationU32, TimerInstantU32},
+        };
+        let at = TimerInstantU32::<1_000>::from_ticks;
+        let mut matcher = Matcher::new([
+            Command {
+                name: "chord",
+                pattern: Pattern::Chord {
+                    buttons: 0b011,
+                    hold: TimerDurationU32::from_ticks(3000),
+                },
+                action: || {},
+            },
+            Command {
+                name: "sequence",
+                pattern: Pattern::Sequence {
+                    buttons: &[2, 1, 2],
+                    gap: TimerDurationU32::from_ticks(1000),
+                },
+                action: || {},
+            },
+        ]);
+
+        // Held together long enough, once
+        assert_eq!(matcher.feed(0, Event::Pressed, at(0)), None);
+        assert_eq!(matcher.feed(1, Event::Pressed, at(100)), None);
+        assert_eq!(matcher.tick(at(3000)), None);
+        assert_eq!(matcher.tick(at(3100)), Some("chord"));
+        assert_eq!(matcher.tick(at(3200)), None);
+        // A third button spoils it
+        matcher.feed(1, Event::Released, at(3300));
+        matcher.feed(1, Event::Pressed, at(3400));
+        matcher.feed(2, Event::Pressed, at(3500));
+        assert_eq!(matcher.tick(at(9000)), None);
+        for b in 0..3 {
+            matcher.feed(b, Event::Released, at(9000));
+        }
+
+        // Too slow, then a wrong button restarting it, then right
+        matcher.feed(2, Event::Pressed, at(10_000));
+        matcher.feed(1, Event::Pressed, at(11_500));
+        assert_eq!(matcher.feed(2, Event::Pressed, at(12_000)), None);
+        matcher.feed(1, Event::Pressed, at(12_500));
+        assert_eq!(
+            matcher.feed(2, Event::Pressed, at(13_000)),
+            Some("sequence")
+        );
+
+        // An extra press of a repeated button keeps the part that still fits
+        let mut matcher = Matcher::new([Command {
+            name: "repeated",
+            pattern: Pattern::Sequence {
+                buttons: &[0, 0, 1],
+                gap: TimerDurationU32::from_ticks(1000),
+            },
+            action: || {},
+        }]);
+        for t in [0, 100, 200] {
+            assert_eq!(matcher.feed(0, Event::Pressed, at(t)), None);
+        }
+        assert_eq!(matcher.feed(1, Event::Pressed, at(300)), Some("repeated"));
+    }
+
+    #[test]
+    fn combo_rejects_empty_sequence() {
+        use nrf_play::{
+            combo::{Command, Pattern},
+            mono::fugit::TimerDurationU32,
+        };
+        let sequence = |buttons: &'static [u8]| Command::<1_000> {
+            name: "sequence",
+            pattern: Pattern::Sequence {
+                buttons,
+                gap: TimerDurationU32::from_ticks(1000),
+            },
+            action: || {},
+        };
+        // `Matcher::new` panics on these
+        assert!(!sequence(&[]).is_valid());
+        assert!(sequence(&[0]).is_valid());
+    }
+
+    #[test]
+    fn morse_adapts_to_speed() {
+        use nrf_play::morse::{Decoder, Element};
+        // Sends `code` with `unit` ms dots, ending with a letter gap
+        fn send(decoder: &mut Decoder, code: &str, unit: u32) {
+            for (i, c) in code.chars().enumerate() {
+                if i > 0 {
+                    decoder.silence(unit);
+                }
+                decoder.press(if c == '-' { 3 * unit } else { unit });
+            }
+            decoder.silence(3 * unit);
+        }
+        let mut decoder = Decoder::new(20);
+
+        send(&mut decoder, "...", 60);
+        send(&mut decoder, "---", 60);
+        send(&mut decoder, "...", 60);
+        decoder.silence(7 * 60);
+        for c in "SOS ".chars() {
+            assert_eq!(decoder.next_char(), Some(c));
+        }
+        assert!(decoder.is_idle());
+
+        // Slowing down to 8 WPM, a few letters in it follows
+        for _ in 0..4 {
+            send(&mut decoder, ".-", 150);
+        }
+        assert_eq!(decoder.wpm(), 8);
+        while decoder.next_char().is_some() {}
+        assert_eq!(decoder.press(200), Element::Dot);
+        assert_eq!(decoder.press(400), Element::Dash);
+        decoder.silence(1000);
+        assert_eq!(decoder.next_char(), Some('A'));
+        assert_eq!(decoder.next_char(), Some(' '));
+
+        send(&mut decoder, "........", 150);
+        assert_eq!(decoder.next_char(), Some('?'));
+    }
+
+    #[test]
+    fn led_patterns() {
+        use nrf_play::pattern::{frame_steps, Layers, Pattern, MAX_STEPS};
+        let blink = Pattern::Blink {
+            on_ms: 100,
+            off_ms: 100,
+        };
+        assert_eq!(blink.steps(), 10);
+        assert_eq!(blink.level(4), 255);
+        assert_eq!(blink.level(5), 0);
+        assert_eq!(blink.level(14), 255);
+
+        // Code 20: two flashes, a pause, a long flash and the long pause
+        let code = Pattern::Code(20);
+        assert_eq!(code.steps(), 30 + 30 + 50 + 70);
+        assert_eq!(code.level(0), 255);
+        assert_eq!(code.level(15), 0);
+        assert_eq!(code.level(25), 255);
+        assert_eq!(code.level(59), 0);
+        assert_eq!(code.level(60), 255);
+        assert_eq!(code.level(109), 255);
+        assert_eq!(code.level(110), 0);
+
+        let breathe = Pattern::Breathe { period_ms: 2000 };
+        assert_eq!(breathe.level(0), 0);
+        assert_eq!(breathe.level(50), 255);
+        assert!(breathe.level(25) < 128);
+
+        // Frames loop all patterns cleanly when they can
+        assert_eq!(frame_steps(&[blink, breathe, Pattern::Off]), 100);
+        assert_eq!(frame_steps(&[blink, Pattern::Flashes { count: 2 }]), 100);
+        // Too long together, the longest pattern decides
+        assert_eq!(frame_steps(&[code, breathe]), code.steps());
+        assert!(code.steps() * breathe.steps() > MAX_STEPS);
+
+        let mut layers = Layers::<2>::new();
+        assert_eq!(layers.current(), Pattern::Off);
+        layers.set(0, blink);
+        layers.set(5, code);
+        assert_eq!(layers.current(), code);
+        // Full, the lowest priority makes room for higher ones only
+        assert!(layers.set(1, Pattern::On(10)));
+        assert!(!layers.set(0, blink));
+        assert_eq!(layers.current(), code);
+        layers.clear(5);
+        assert_eq!(layers.current(), Pattern::On(10));
+    }
+
+    #[test]
+    fn rgb_gamma_and_current() {
+        use nrf_play::rgb::{current_ma, gamma, Rgb};
+
+        assert_eq!(gamma(0), 0);
+        assert_eq!(gamma(255), 255);
+        // Dark values are pushed down, the curve stays monotonic
+        assert!(gamma(64) < 16);
+        assert!(gamma(128) < 64);
+        assert!((1..=255).all(|x| gamma(x) >= gamma(x - 1)));
+
+        assert_eq!(Rgb::WHITE.scale(128), Rgb::new(128, 128, 128));
+        assert_eq!(Rgb::GREEN.mix(Rgb::RED, 0), Rgb::GREEN);
+        assert_eq!(Rgb::GREEN.mix(Rgb::RED, 255), Rgb::RED);
+
+        // 20 mA per channel at full
+        assert_eq!(current_ma([Rgb::WHITE; 8]), 8 * 60);
+        assert_eq!(current_ma([Rgb::RED, Rgb::BLACK]), 20);
+        assert_eq!(current_ma([Rgb::BLACK; 0]), 0);
+    }
+
+    #[test]
+    fn melody_parsing_and_priorities() {
+        use nrf_play::melody::{note_hz, Error, Melody, Player, Sound, Tone, GAP_MS};
+
+        assert_eq!(note_hz(9, 4), 440);
+        assert_eq!(note_hz(9, 5), 880);
+        assert_eq!(note_hz(0, 4), 262);
+
+        // 120 bpm: a quarter note is 500 ms, notes are followed by their gap
+        let mut tones = Melody::new("a c#5/8 bb3/2. r/16", 120).tones();
+        let mut next = || tones.next().map(Result::unwrap);
+        assert_eq!(next(), Some(Tone::new(440, 500 - GAP_MS)));
+        assert_eq!(next(), Some(Tone::rest(GAP_MS)));
+        assert_eq!(next(), Some(Tone::new(554, 250 - GAP_MS)));
+        assert_eq!(next(), Some(Tone::rest(GAP_MS)));
+        assert_eq!(next(), Some(Tone::new(233, 1500 - GAP_MS)));
+        assert_eq!(next(), Some(Tone::rest(GAP_MS)));
+        assert_eq!(next(), Some(Tone::rest(125)));
+        assert_eq!(next(), None);
+
+        assert_eq!(Melody::new("c e g", 100).check(), Ok(()));
+        assert_eq!(Melody::new("c h g", 100).check(), Err(Error::BadNote(2)));
+        assert_eq!(Melody::new("c  e/3", 100).check(), Err(Error::BadNote(3)));
+        assert_eq!(Melody::new("c9", 100).check(), Err(Error::BadNote(0)));
+
+        let beep = Sound::Tone(Tone::new(2000, 30));
+        let alarm = Sound::Melody(Melody::new("a5 r", 120));
+        let mut player = Player::new();
+        assert!(player.play(0, beep, false));
+        assert_eq!(player.next_tone(), Some(Tone::new(2000, 30)));
+        assert_eq!(player.next_tone(), None);
+        assert_eq!(player.priority(), None);
+
+        // The alarm repeats and beeps can't cut it
+        assert!(player.play(2, alarm, true));
+        assert!(!player.play(0, beep, false));
+        let alarm_tones = [
+            Tone::new(880, 500 - GAP_MS),
+            Tone::rest(GAP_MS),
+            Tone::rest(500),
+        ];
+        for tone in alarm_tones.iter().chain(alarm_tones.iter()) {
+            assert_eq!(player.next_tone(), Some(*tone));
+        }
+        assert!(!player.stop(0));
+        assert!(player.stop(2));
+        assert_eq!(player.next_tone(), None);
+    }
 }